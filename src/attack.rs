@@ -0,0 +1,271 @@
+use board::{Board, Tile};
+use color::Color;
+use piece::{Piece, Rank};
+use ply::Location;
+
+pub(crate) const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2), (2, 1), (2, -1), (1, -2),
+    (-1, -2), (-2, -1), (-2, 1), (-1, 2),
+];
+pub(crate) const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0), (1, 1), (0, 1), (-1, 1),
+    (-1, 0), (-1, -1), (0, -1), (1, -1),
+];
+pub(crate) const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+pub(crate) const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+impl Board {
+    /// Every tile attacked by a piece of the given color, indexed
+    /// like the grid. A tile holding a piece of the same color is
+    /// still attacked if that piece is defended.
+    pub fn attack_map(&self, by: Color) -> [[bool; 8]; 8] {
+        let mut map = [[false; 8]; 8];
+        for rank in 0..8u8 {
+            for file in 0..8u8 {
+                let from = Location { rank, file };
+                let piece = match *self.tile_at(&from) {
+                    Tile::Taken(piece) if piece.color == by => piece,
+                    _ => continue,
+                };
+
+                let mut mark = |location: Location| {
+                    map[location.rank as usize][location.file as usize] = true;
+                };
+                match piece.rank {
+                    Rank::Pawn => {
+                        let forward = match by { Color::White => 1, Color::Black => -1 };
+                        self.step_attacks(from, &[(forward, -1), (forward, 1)], &mut mark);
+                    },
+                    Rank::Knight => self.step_attacks(from, &KNIGHT_OFFSETS, &mut mark),
+                    Rank::King   => self.step_attacks(from, &KING_OFFSETS, &mut mark),
+                    Rank::Bishop => self.slide_attacks(from, &BISHOP_DIRECTIONS, &mut mark),
+                    Rank::Rook   => self.slide_attacks(from, &ROOK_DIRECTIONS, &mut mark),
+                    Rank::Queen  => {
+                        self.slide_attacks(from, &BISHOP_DIRECTIONS, &mut mark);
+                        self.slide_attacks(from, &ROOK_DIRECTIONS, &mut mark);
+                    },
+                }
+            }
+        }
+        map
+    }
+
+    /// Whether the king of the side to move is under attack.
+    pub fn is_check(&self) -> bool {
+        match self.find_king(self.color) {
+            Some(king) => self.attack_map(self.color.other())[king.rank as usize][king.file as usize],
+            None       => false,
+        }
+    }
+
+    /// Whether the side to move is in check with no legal plies.
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_plies().is_empty()
+    }
+
+    /// Whether the side to move is not in check, but has no legal plies.
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_plies().is_empty()
+    }
+
+    fn step_attacks<F: FnMut(Location)>(&self, from: Location, offsets: &[(i8, i8)], mark: &mut F) {
+        for &(ranks, files) in offsets {
+            if let Some(to) = from.offset(ranks, files) {
+                mark(to);
+            }
+        }
+    }
+
+    fn slide_attacks<F: FnMut(Location)>(&self, from: Location, directions: &[(i8, i8)], mark: &mut F) {
+        for &(ranks, files) in directions {
+            let mut next = from.offset(ranks, files);
+            while let Some(to) = next {
+                mark(to);
+                if *self.tile_at(&to) != Tile::Empty {
+                    break;
+                }
+                next = to.offset(ranks, files);
+            }
+        }
+    }
+
+    pub(crate) fn find_king(&self, color: Color) -> Option<Location> {
+        let king = Tile::Taken(Piece { rank: Rank::King, color });
+        for rank in 0..8u8 {
+            for file in 0..8u8 {
+                let location = Location { rank, file };
+                if *self.tile_at(&location) == king {
+                    return Some(location);
+                }
+            }
+        }
+        None
+    }
+
+    /// The locations of the pieces of the given color that attack the location.
+    pub fn attackers(&self, location: Location, by: Color) -> Vec<Location> {
+        self.find_attackers(location, by, None)
+    }
+
+    /// Whether any piece of the given color attacks the location.
+    pub fn is_attacked(&self, location: Location, by: Color) -> bool {
+        self.scan_attackers(location, by, None, |_| true)
+    }
+
+    /// The attackers of the location if the piece at `through` were not
+    /// on the board, revealing the pieces that line up behind it.
+    pub fn xray_attackers(&self, location: Location, by: Color, through: Location) -> Vec<Location> {
+        self.find_attackers(location, by, Some(through))
+    }
+
+    fn find_attackers(&self, location: Location, by: Color, through: Option<Location>) -> Vec<Location> {
+        let mut attackers = Vec::new();
+        self.scan_attackers(location, by, through, |square| {
+            attackers.push(square);
+            false
+        });
+        attackers
+    }
+
+    // Pass each attacker to `found` until it returns true, and return
+    // whether it did.
+    fn scan_attackers<F: FnMut(Location) -> bool>(&self, location: Location, by: Color, through: Option<Location>, mut found: F) -> bool {
+        let is = |location: Location, ranks: &[Rank]| {
+            match *self.tile_at(&location) {
+                Tile::Taken(piece) => {
+                    Some(location) != through && piece.color == by && ranks.contains(&piece.rank)
+                },
+                Tile::Empty => false,
+            }
+        };
+
+        // A pawn attacks diagonally forward, so look diagonally backward.
+        let backward = match by { Color::White => -1, Color::Black => 1 };
+        let steps: [(&[(i8, i8)], Rank); 3] = [
+            (&[(backward, -1), (backward, 1)], Rank::Pawn),
+            (&KNIGHT_OFFSETS, Rank::Knight),
+            (&KING_OFFSETS, Rank::King),
+        ];
+        for &(offsets, rank) in steps.iter() {
+            for &(r, f) in offsets {
+                match location.offset(r, f) {
+                    Some(square) if is(square, &[rank]) && found(square) => return true,
+                    _ => (),
+                }
+            }
+        }
+
+        let slides = [(ROOK_DIRECTIONS, Rank::Rook), (BISHOP_DIRECTIONS, Rank::Bishop)];
+        for &(directions, rank) in slides.iter() {
+            let ranks = [rank, Rank::Queen];
+            for &(r, f) in directions.iter() {
+                let mut next = location.offset(r, f);
+                while let Some(square) = next {
+                    if *self.tile_at(&square) != Tile::Empty && Some(square) != through {
+                        if is(square, &ranks) && found(square) {
+                            return true;
+                        }
+                        break;
+                    }
+                    next = square.offset(r, f);
+                }
+            }
+        }
+        false
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use color::Color;
+    use ply::Location;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation};
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    #[test]
+    fn attack_map_initial() {
+        let map = Board::new().attack_map(Color::White);
+        assert_eq!(map[0], [false, true, true, true, true, true, true, false]);
+        assert_eq!(map[1], [true; 8]);
+        assert_eq!(map[2], [true; 8]);
+        assert_eq!(map[3], [false; 8]);
+
+        let map = Board::new().attack_map(Color::Black);
+        assert_eq!(map[5], [true; 8]);
+        assert_eq!(map[4], [false; 8]);
+    }
+
+    #[test]
+    fn attack_map_blocked() {
+        let map = from_fen("4k3/8/8/8/R2p4/8/8/4K3 w - - 0 1").attack_map(Color::White);
+        assert!(map[3][1] && map[3][2] && map[3][3]);
+        assert!(!map[3][4]);
+        assert!(map[7][0] && map[0][0]);
+    }
+
+    #[test]
+    fn check() {
+        assert!(!Board::new().is_check());
+        let board = from_fen("rnbqkbnr/ppppp1pp/8/5p1Q/4P3/8/PPPP1PPP/RNB1KBNR b KQkq - 1 2");
+        assert!(board.is_check());
+        assert!(!board.is_checkmate());
+        assert!(!board.is_stalemate());
+    }
+
+    #[test]
+    fn checkmate() {
+        let board = from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert!(board.is_check());
+        assert!(board.is_checkmate());
+        assert!(!board.is_stalemate());
+    }
+
+    #[test]
+    fn stalemate() {
+        let board = from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert!(!board.is_check());
+        assert!(!board.is_checkmate());
+        assert!(board.is_stalemate());
+    }
+
+    #[test]
+    fn attackers() {
+        // Kiwipete: f7 is hit by the knight on e5 and guarded by the king and queen.
+        let board = from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let f7 = Location { file: 5, rank: 6 };
+        assert_eq!(board.attackers(f7, Color::White), vec![Location { file: 4, rank: 4 }]);
+        let mut defenders = board.attackers(f7, Color::Black);
+        defenders.sort_by_key(|location| (location.rank, location.file));
+        assert_eq!(defenders, vec![Location { file: 4, rank: 6 }, Location { file: 4, rank: 7 }]);
+
+        // The pawn on b4 attacks c3, and so does the pawn on h3 attack g2.
+        assert!(board.is_attacked(Location { file: 2, rank: 2 }, Color::Black));
+        assert!(board.is_attacked(Location { file: 6, rank: 1 }, Color::Black));
+        assert!(!board.is_attacked(Location { file: 6, rank: 2 }, Color::Black));
+
+        for rank in 0..8 {
+            for file in 0..8 {
+                let location = Location { rank, file };
+                for &color in [Color::White, Color::Black].iter() {
+                    assert_eq!(board.is_attacked(location, color), !board.attackers(location, color).is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn xray_attackers() {
+        let board = from_fen("4k3/8/8/3q4/8/8/3R4/3RK3 w - - 0 1");
+        let d5 = Location { file: 3, rank: 4 };
+        let d2 = Location { file: 3, rank: 1 };
+        assert_eq!(board.attackers(d5, Color::White), vec![d2]);
+        assert_eq!(board.xray_attackers(d5, Color::White, d2), vec![Location { file: 3, rank: 0 }]);
+        assert!(board.attackers(Location { file: 3, rank: 0 }, Color::Black).is_empty());
+        assert_eq!(board.xray_attackers(Location { file: 3, rank: 0 }, Color::Black, d2), vec![d5]);
+    }
+}
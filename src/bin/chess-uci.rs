@@ -0,0 +1,231 @@
+extern crate chess;
+
+use std::io;
+use std::io::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use chess::{Board, Color, Game};
+use chess::engine::{self, Limits, SearchResult, MATE, MAX_DEPTH};
+use chess::notation::{BoardInputNotation, ForsythEdwardsNotation, LongAlgebraicNotation, PlyInputNotation, PlyOutputNotation};
+
+/// How many more moves to budget the clock for, when the GUI doesn't say.
+const MOVES_TO_GO: u64 = 30;
+
+/// A search running in the background, and the flag that stops it.
+struct Search {
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Search {
+    fn finish(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.thread().unpark();
+        self.thread.join().expect("The search thread panicked.");
+    }
+}
+
+
+// Speak the Universal Chess Interface on stdin and stdout.
+// http://wbec-ridderkerk.nl/html/UCIProtocol.html
+fn main() {
+    let mut game = Game::new();
+    let mut search: Option<Search> = None;
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.expect("Could not read from stdin.");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            Some("uci") => {
+                println!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+                println!("id author {}", env!("CARGO_PKG_AUTHORS"));
+                println!("uciok");
+            },
+            Some("isready")    => println!("readyok"),
+            Some("ucinewgame") => {
+                if let Some(search) = search.take() {
+                    search.finish();
+                }
+                game = Game::new();
+            },
+            Some("position") => {
+                if let Some(search) = search.take() {
+                    search.finish();
+                }
+                match position(&tokens[1..]) {
+                    Ok(position) => game = position,
+                    Err(error)   => println!("info string {}", error),
+                }
+            },
+            Some("go") => {
+                if let Some(search) = search.take() {
+                    search.finish();
+                }
+                search = Some(go(&game.board, &tokens[1..]));
+            },
+            Some("stop") => {
+                if let Some(search) = search.take() {
+                    search.finish();
+                }
+            },
+            Some("setoption") => {
+                let name = tokens.iter().skip_while(|&&token| token != "name").skip(1)
+                    .take_while(|&&token| token != "value")
+                    .copied().collect::<Vec<&str>>().join(" ");
+                println!("info string Unknown option: {}", name);
+            },
+            Some("quit") => break,
+            // Nothing to do for the rest of what GUIs tell engines.
+            Some("debug") | Some("register") | Some("ponderhit") | None => (),
+            Some(command) => println!("info string Unknown command: {}", command),
+        }
+        io::stdout().flush().expect("Could not write to stdout.");
+    }
+
+    if let Some(search) = search.take() {
+        search.finish();
+    }
+}
+
+// Set up the game from `startpos` or `fen <fen>`, then play any plies
+// that follow `moves`.
+fn position(tokens: &[&str]) -> Result<Game, String> {
+    let moves = tokens.iter().position(|&token| token == "moves").unwrap_or(tokens.len());
+    let board = match tokens.first().copied() {
+        Some("startpos") => Board::new(),
+        Some("fen")      => ForsythEdwardsNotation.parse_board(&tokens[1..moves].join(" "))
+            .map_err(|error| format!("Invalid FEN: {}", error))?,
+        _                => return Err("Expected startpos or fen.".to_string()),
+    };
+
+    let mut game = Game::from_board(board);
+    for token in tokens.iter().skip(moves + 1) {
+        let ply = LongAlgebraicNotation.parse_ply(&game.board, token)
+            .ok_or_else(|| format!("Illegal move: {}", token))?;
+        game.play(&ply).map_err(|error| format!("Illegal move {}: {}", token, error))?;
+    }
+    Ok(game)
+}
+
+// Start searching the board in the background, within the limits
+// that the tokens give. An infinite search holds back its best move
+// until it is told to stop.
+fn go(board: &Board, tokens: &[&str]) -> Search {
+    let (mut limits, infinite) = limits(board, tokens);
+    let stop = Arc::new(AtomicBool::new(false));
+    limits.stop = Some(stop.clone());
+    let (board, stopped) = (*board, stop.clone());
+    let thread = thread::spawn(move || {
+        let result = engine::search_with(&board, &limits, |result| info(&board, result));
+        while infinite && !stopped.load(Ordering::Relaxed) {
+            thread::park();
+        }
+        match result {
+            Some(result) => println!("bestmove {}", LongAlgebraicNotation.unparse_ply(&board, &result.best)),
+            None         => println!("bestmove 0000"),
+        }
+        io::stdout().flush().expect("Could not write to stdout.");
+    });
+    Search { stop, thread }
+}
+
+// The limits that the tokens after `go` give, and whether the search
+// is to go on until it is stopped.
+fn limits(board: &Board, tokens: &[&str]) -> (Limits, bool) {
+    let mut limits = Limits::default();
+    let mut infinite = false;
+    let (mut time, mut increment, mut moves_to_go) = (None, 0, MOVES_TO_GO);
+    let (own_time, own_increment) = match board.color {
+        Color::White => ("wtime", "winc"),
+        Color::Black => ("btime", "binc"),
+    };
+    let mut tokens = tokens.iter();
+    while let Some(&token) = tokens.next() {
+        let mut value = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+        match token {
+            "depth"    => limits.depth = value().map(|depth| depth as u32),
+            "nodes"    => limits.nodes = value(),
+            "movetime" => limits.time = value().map(Duration::from_millis),
+            "movestogo" => moves_to_go = value().unwrap_or(MOVES_TO_GO).max(1),
+            "infinite" => infinite = true,
+            _ if token == own_time      => time = value(),
+            _ if token == own_increment => increment = value().unwrap_or(0),
+            _ => (),
+        }
+    }
+
+    // Spend an even share of the clock on each move to come, plus most
+    // of the increment, but never more than half of what is left.
+    if let Some(time) = time {
+        let budget = (time / moves_to_go + increment * 3 / 4).min(time / 2);
+        limits.time = Some(Duration::from_millis(budget.max(1)));
+    }
+    (limits, infinite)
+}
+
+fn info(board: &Board, result: &SearchResult) {
+    let score = if result.score.abs() > MATE - MAX_DEPTH as i32 {
+        let plies = MATE - result.score.abs();
+        format!("mate {}", result.score.signum() * (plies + 1) / 2)
+    } else {
+        format!("cp {}", result.score)
+    };
+    let pv: Vec<String> = result.pv.iter().map(|ply| LongAlgebraicNotation.unparse_ply(board, ply)).collect();
+    println!("info depth {} score {} nodes {} pv {}", result.depth, score, result.nodes, pv.join(" "));
+    io::stdout().flush().expect("Could not write to stdout.");
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use chess::Board;
+    use chess::notation::{BoardInputNotation, BoardOutputNotation, ForsythEdwardsNotation};
+    use super::{limits, position};
+
+    fn tokens(line: &str) -> Vec<&str> {
+        line.split_whitespace().collect()
+    }
+
+    #[test]
+    fn position_moves() {
+        let game = position(&tokens("startpos moves e2e4 e7e5 g1f3")).unwrap();
+        assert_eq!(game.log.len(), 3);
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&game.board),
+                   "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        let game = position(&tokens("fen 4k3/P7/8/8/8/8/8/4K3 w - - 0 1 moves a7a8q e8d7")).unwrap();
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&game.board), "Q7/3k4/8/8/8/8/8/4K3 w - - 1 2");
+
+        assert_eq!(position(&tokens("startpos")).unwrap().board, Board::new());
+        assert!(position(&tokens("startpos moves e2e5")).is_err());
+        assert!(position(&tokens("fen 4k3/8 w - - 0 1")).is_err());
+        assert!(position(&tokens("moves e2e4")).is_err());
+    }
+
+    #[test]
+    fn go_limits() {
+        let white = Board::new();
+        let black = ForsythEdwardsNotation.parse_board("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+
+        let (fixed, infinite) = limits(&white, &tokens("depth 5 nodes 1000"));
+        assert_eq!((fixed.depth, fixed.nodes, fixed.time, infinite), (Some(5), Some(1000), None, false));
+        assert_eq!(limits(&white, &tokens("movetime 250")).0.time, Some(Duration::from_millis(250)));
+
+        // An even share of the clock for each move to go, plus three
+        // quarters of the increment.
+        let go = tokens("wtime 60000 btime 30000 winc 2000 binc 1000 movestogo 20");
+        assert_eq!(limits(&white, &go).0.time, Some(Duration::from_millis(3000 + 1500)));
+        assert_eq!(limits(&black, &go).0.time, Some(Duration::from_millis(1500 + 750)));
+        assert_eq!(limits(&white, &tokens("wtime 30000")).0.time, Some(Duration::from_millis(1000)));
+        // Never more than half of what is left.
+        assert_eq!(limits(&white, &tokens("wtime 1000 winc 5000")).0.time, Some(Duration::from_millis(500)));
+
+        let (unlimited, infinite) = limits(&white, &tokens("infinite"));
+        assert_eq!((unlimited.depth, unlimited.time, infinite), (None, None, true));
+    }
+}
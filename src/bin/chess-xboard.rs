@@ -0,0 +1,345 @@
+extern crate chess;
+
+use std::io;
+use std::io::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chess::{Color, Game, Outcome};
+use chess::engine::{self, Limits, MATE, MAX_DEPTH};
+use chess::notation::{BoardInputNotation, ForsythEdwardsNotation, LongAlgebraicNotation, PlyInputNotation, PlyOutputNotation};
+
+/// How many more moves to budget the clock for, when the time control
+/// doesn't say.
+const MOVES_TO_GO: u64 = 30;
+
+/// A search running in the background. Stopping it still plays the
+/// best ply found so far; abandoning it plays nothing.
+struct Search {
+    stop: Arc<AtomicBool>,
+    abandon: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Search {
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.join().expect("The search thread panicked.");
+    }
+
+    // Raise the flag while holding the game, so that the search has
+    // either moved already or sees the flag before it does.
+    fn abandon(self, game: &Mutex<Game>) {
+        let guard = game.lock().unwrap();
+        self.abandon.store(true, Ordering::Relaxed);
+        drop(guard);
+        self.stop();
+    }
+}
+
+/// Everything the engine has been told.
+struct Engine {
+    game: Arc<Mutex<Game>>,
+    search: Option<Search>,
+    /// The color the engine plays, if it isn't in force mode
+    color: Option<Color>,
+    post: bool,
+    depth: Option<u32>,
+    /// Fixed time for each move, from `st`
+    move_time: Option<Duration>,
+    /// Moves per time control, and the increment, from `level`
+    moves_per_control: u64,
+    increment: Duration,
+    /// The engine's clock, from `time`
+    clock: Option<Duration>,
+}
+
+
+// Speak the Chess Engine Communication Protocol on stdin and stdout.
+// https://www.gnu.org/software/xboard/engine-intf.html
+fn main() {
+    let mut engine = Engine::new();
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.expect("Could not read from stdin.");
+        let (command, arguments) = match line.trim().find(' ') {
+            Some(index) => (&line.trim()[..index], line.trim()[index..].trim()),
+            None        => (line.trim(), ""),
+        };
+
+        match command {
+            "protover" => {
+                println!("feature myname=\"{} {}\" usermove=1 setboard=1 ping=1 playother=1 colors=0 sigint=0 sigterm=0 done=1",
+                         env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            },
+            "ping" => {
+                engine.abandon();
+                println!("pong {}", arguments);
+            },
+            "new" => {
+                engine.abandon();
+                *engine.game.lock().unwrap() = Game::new();
+                engine.color = Some(Color::Black);
+                engine.depth = None;
+                engine.move_time = None;
+            },
+            "setboard" => {
+                engine.abandon();
+                match ForsythEdwardsNotation.parse_board(arguments) {
+                    Ok(board)  => *engine.game.lock().unwrap() = Game::from_board(board),
+                    Err(error) => println!("tellusererror Illegal position: {}", error),
+                }
+            },
+            "usermove" => {
+                engine.abandon();
+                engine.usermove(arguments);
+            },
+            "go" => {
+                engine.abandon();
+                engine.color = Some(engine.game.lock().unwrap().board.color);
+                engine.think();
+            },
+            "playother" => {
+                engine.abandon();
+                engine.color = Some(engine.game.lock().unwrap().board.color.other());
+            },
+            "force" | "result" => {
+                engine.abandon();
+                engine.color = None;
+            },
+            "?" => if let Some(search) = engine.search.take() {
+                search.stop();
+            },
+            "undo" => {
+                engine.abandon();
+                engine.game.lock().unwrap().undo();
+            },
+            "remove" => {
+                engine.abandon();
+                let mut game = engine.game.lock().unwrap();
+                game.undo();
+                game.undo();
+            },
+            "level" => engine.level(arguments),
+            "st" => engine.move_time = arguments.parse().ok().map(Duration::from_secs),
+            "sd" => engine.depth = arguments.parse().ok(),
+            "time" => engine.clock = arguments.parse::<u64>().ok().map(|centiseconds| Duration::from_millis(centiseconds * 10)),
+            "post" => engine.post = true,
+            "nopost" => engine.post = false,
+            "quit" => break,
+            // Nothing to do for the rest of what xboard tells engines.
+            "xboard" | "accepted" | "rejected" | "otim" | "random" | "hard" | "easy" | "computer"
+                | "name" | "rating" | "ics" | "." | "" => (),
+            _ => println!("Error (unknown command): {}", command),
+        }
+        io::stdout().flush().expect("Could not write to stdout.");
+    }
+
+    engine.abandon();
+}
+
+impl Engine {
+    fn new() -> Engine {
+        Engine {
+            game: Arc::new(Mutex::new(Game::new())),
+            search: None,
+            color: Some(Color::Black),
+            post: false,
+            depth: None,
+            move_time: None,
+            moves_per_control: 0,
+            increment: Duration::from_secs(0),
+            clock: None,
+        }
+    }
+
+    fn abandon(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.abandon(&self.game);
+        }
+    }
+
+    fn usermove(&mut self, input: &str) {
+        let mut game = self.game.lock().unwrap();
+        let ply = match LongAlgebraicNotation.parse_ply(&game.board, input) {
+            Some(ply) => ply,
+            None      => {
+                println!("Illegal move: {}", input);
+                return;
+            },
+        };
+        if let Err(error) = game.play(&ply) {
+            println!("Illegal move ({}): {}", error, input);
+            return;
+        }
+        if let Some(outcome) = game.outcome() {
+            println!("{} {{{}}}", outcome.score(), outcome);
+            return;
+        }
+        drop(game);
+        self.think();
+    }
+
+    // `level MPS BASE INC`, where BASE is in minutes, or minutes and
+    // seconds like 0:30, and INC is in seconds.
+    fn level(&mut self, arguments: &str) {
+        let fields: Vec<&str> = arguments.split_whitespace().collect();
+        if fields.len() != 3 {
+            println!("Error (bad level): {}", arguments);
+            return;
+        }
+        self.moves_per_control = fields[0].parse().unwrap_or(0);
+        let base: Vec<u64> = fields[1].split(':').map(|part| part.parse().unwrap_or(0)).collect();
+        let seconds = base[0] * 60 + base.get(1).copied().unwrap_or(0);
+        self.clock = Some(Duration::from_secs(seconds));
+        self.increment = Duration::from_millis((fields[2].parse::<f64>().unwrap_or(0.0) * 1000.0) as u64);
+        self.move_time = None;
+    }
+
+    // How long to think about the next move: the fixed time if there is
+    // one, otherwise an even share of the clock for each move left in
+    // the time control, plus most of the increment.
+    fn budget(&self, fullmove_number: u32) -> Option<Duration> {
+        if self.move_time.is_some() {
+            return self.move_time;
+        }
+        let clock = self.clock?;
+        let moves_to_go = match self.moves_per_control {
+            0     => MOVES_TO_GO,
+            moves => moves - (fullmove_number as u64 - 1) % moves,
+        };
+        Some((clock / moves_to_go as u32 + self.increment * 3 / 4).min(clock / 2))
+    }
+
+    // Start thinking in the background, if it is the engine's turn.
+    fn think(&mut self) {
+        let board = {
+            let game = self.game.lock().unwrap();
+            if self.color != Some(game.board.color) || game.outcome().is_some() {
+                return;
+            }
+            game.board
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let abandon = Arc::new(AtomicBool::new(false));
+        let limits = Limits {
+            depth: self.depth,
+            nodes: None,
+            time: self.budget(board.fullmove_number),
+            stop: Some(stop.clone()),
+        };
+        let (game, post, abandoned) = (self.game.clone(), self.post, abandon.clone());
+        let thread = thread::spawn(move || {
+            let start = Instant::now();
+            let result = engine::search_with(&board, &limits, |result| if post {
+                let pv: Vec<String> = result.pv.iter().map(|ply| LongAlgebraicNotation.unparse_ply(&board, ply)).collect();
+                println!("{} {} {} {} {}", result.depth, score(result.score), start.elapsed().as_millis() / 10, result.nodes, pv.join(" "));
+                io::stdout().flush().expect("Could not write to stdout.");
+            });
+            let result = match result {
+                Some(result) => result,
+                None         => return,
+            };
+
+            let mut game = game.lock().unwrap();
+            if abandoned.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Some(draw) = claim(&game, result.score) {
+                println!("{} {{{}}}", draw.score(), draw);
+            } else if game.play(&result.best).is_ok() {
+                println!("move {}", LongAlgebraicNotation.unparse_ply(&board, &result.best));
+                if let Some(outcome) = game.outcome().or_else(|| claim(&game, result.score)) {
+                    println!("{} {{{}}}", outcome.score(), outcome);
+                }
+            }
+            io::stdout().flush().expect("Could not write to stdout.");
+        });
+        self.search = Some(Search { stop, abandon, thread });
+    }
+}
+
+// Post scores in centipawns, with mates as 100000 plus the number of
+// moves to mate, negated when the engine is the one getting mated.
+fn score(score: i32) -> i32 {
+    if score.abs() > MATE - MAX_DEPTH as i32 {
+        let moves = (MATE - score.abs() + 1) / 2;
+        score.signum() * (100_000 + moves)
+    } else {
+        score
+    }
+}
+
+// The draw to claim, if there is one and the search doesn't expect to
+// win. The search can't see repetitions, so leave a won position to it.
+fn claim(game: &Game, score: i32) -> Option<Outcome> {
+    game.claimable_draw().filter(|_| score <= 0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use chess::{Game, Outcome, Reason};
+    use chess::engine::MATE;
+    use chess::notation::{BoardInputNotation, ForsythEdwardsNotation};
+    use super::{claim, score, Engine};
+
+    #[test]
+    fn level() {
+        let mut engine = Engine::new();
+        engine.level("40 5 0");
+        assert_eq!((engine.moves_per_control, engine.clock, engine.increment),
+                   (40, Some(Duration::from_secs(300)), Duration::from_secs(0)));
+        engine.level("0 0:30 0.5");
+        assert_eq!((engine.moves_per_control, engine.clock, engine.increment),
+                   (0, Some(Duration::from_secs(30)), Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn budget() {
+        let mut engine = Engine::new();
+        assert_eq!(engine.budget(1), None);
+
+        // An even share of the clock for each move left in the control.
+        engine.level("40 5 0");
+        assert_eq!(engine.budget(1), Some(Duration::from_millis(7500)));
+        assert_eq!(engine.budget(31), Some(Duration::from_secs(30)));
+        assert_eq!(engine.budget(41), Some(Duration::from_millis(7500)));
+
+        // Plus three quarters of the increment, but never more than
+        // half the clock.
+        engine.level("0 1 2");
+        assert_eq!(engine.budget(1), Some(Duration::from_secs(2) + Duration::from_millis(1500)));
+        engine.clock = Some(Duration::from_secs(2));
+        assert_eq!(engine.budget(1), Some(Duration::from_secs(1)));
+
+        engine.move_time = Some(Duration::from_secs(3));
+        assert_eq!(engine.budget(1), Some(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn mate_scores() {
+        assert_eq!(score(35), 35);
+        assert_eq!(score(-120), -120);
+        assert_eq!(score(MATE - 1), 100_001);
+        assert_eq!(score(MATE - 3), 100_002);
+        assert_eq!(score(2 - MATE), -100_001);
+    }
+
+    #[test]
+    fn claims_only_when_not_winning() {
+        let board = ForsythEdwardsNotation.parse_board("4k3/8/8/8/8/8/4P3/R3K3 w - - 100 80").unwrap();
+        let game = Game::from_board(board);
+        assert_eq!(game.outcome(), None);
+        let draw = Outcome { winner: None, reason: Reason::FiftyMoveRule };
+        assert_eq!(claim(&game, 0), Some(draw));
+        assert_eq!(claim(&game, -50), Some(draw));
+        assert_eq!(claim(&game, 500), None);
+        assert_eq!(claim(&Game::new(), 0), None);
+    }
+}
@@ -1,26 +1,485 @@
 extern crate chess;
 
+use std::env;
+use std::fs;
 use std::io;
 use std::io::prelude::*;
+use std::process;
+use std::time::Duration;
+
+use chess::{Board, Color, Game, IllegalPly, Location, Move, Outcome, Piece, Ply, Rank, Reason, Tile};
+use chess::engine::{self, Limits};
+use chess::notation::{BoardInputNotation, BoardOutputNotation, PlyInputNotation, PlyOutputNotation};
+use chess::notation::{BoardOutputNotationHelper, CoordinateNotation, DisplayBoardNotation, ForsythEdwardsNotation};
+use chess::notation::{LongAlgebraicNotation, StandardAlgebraicNotation, UnicodeNotation};
+use chess::notation::pgn::{PgnGame, PgnReader};
+
+const COMMANDS: &str = "  moves   list the legal moves
+  undo    take back the last move, and any engine reply to it
+  flip    turn the board around
+  fen     show the position in Forsyth-Edwards Notation
+  pgn     show the game so far in Portable Game Notation
+  draw    claim a draw by threefold repetition or the fifty-move rule
+  resign  give up the game
+  help    show this message
+  quit    leave without finishing the game";
+
+const USAGE: &str = "\
+Usage: chessref [options]
+  --white human|engine     who plays White (default human)
+  --black human|engine     who plays Black (default human)
+  --depth N                how many plies deep the engine searches
+  --time SECONDS           how long the engine thinks about each move (default 1)
+  --fen FEN                start from the position FEN describes
+  --pgn FILE               start from the end of the first game in FILE
+  --ply N                  with --pgn, start after the first N plies instead
+  --notation san|coord|uci how moves are written (default san)
+  --unicode                draw the board with chess glyphs and box-drawing lines";
+
+/// Who makes the moves for one side.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Player {
+    Human,
+    Engine,
+}
+
+/// The notations moves can be read and written in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Notation {
+    San,
+    Coord,
+    Uci,
+}
+
+impl Notation {
+    fn input(&self) -> &'static dyn PlyInputNotation {
+        match *self {
+            Notation::San   => &StandardAlgebraicNotation,
+            Notation::Coord => &CoordinateNotation,
+            Notation::Uci   => &LongAlgebraicNotation,
+        }
+    }
+
+    fn output(&self) -> &'static dyn PlyOutputNotation {
+        match *self {
+            Notation::San   => &StandardAlgebraicNotation,
+            Notation::Coord => &CoordinateNotation,
+            Notation::Uci   => &LongAlgebraicNotation,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match *self {
+            Notation::San   => "Standard Algebraic Notation, like e4, Nf3 or O-O",
+            Notation::Coord => "coordinates, like e2 e4, g1 f3 or e1 g1",
+            Notation::Uci   => "UCI notation, like e2e4, g1f3 or e7e8q",
+        }
+    }
+}
+
+/// Where the game starts from.
+enum Start {
+    New,
+    Fen(String),
+    /// A PGN file, and how many plies of its first game to play
+    Pgn(String, Option<usize>),
+}
+
+/// The settings given on the command line.
+struct Options {
+    white: Player,
+    black: Player,
+    limits: Limits,
+    start: Start,
+    notation: Notation,
+    unicode: bool,
+}
+
+impl Options {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut options = Options {
+            white: Player::Human,
+            black: Player::Human,
+            limits: Limits { time: Some(Duration::from_secs(1)), ..Limits::default() },
+            start: Start::New,
+            notation: Notation::San,
+            unicode: false,
+        };
+        let mut ply = None;
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+            match &arg[..] {
+                "--white" => options.white = player(&value()?)?,
+                "--black" => options.black = player(&value()?)?,
+                "--depth" => {
+                    let depth = value()?;
+                    options.limits.depth = Some(depth.parse().map_err(|_| format!("Invalid depth: {}", depth))?);
+                    options.limits.time = None;
+                },
+                "--time" => {
+                    let time = value()?;
+                    let seconds: f64 = time.parse().map_err(|_| format!("Invalid time: {}", time))?;
+                    options.limits.time = Some(Duration::from_millis((seconds * 1000.0) as u64));
+                },
+                "--fen" => options.start = Start::Fen(value()?),
+                "--pgn" => options.start = Start::Pgn(value()?, None),
+                "--ply" => {
+                    let value = value()?;
+                    ply = Some(value.parse().map_err(|_| format!("Invalid ply: {}", value))?);
+                },
+                "--notation" => options.notation = match &value()?[..] {
+                    "san"   => Notation::San,
+                    "coord" => Notation::Coord,
+                    "uci"   => Notation::Uci,
+                    other   => return Err(format!("Expected san, coord or uci, not {}", other)),
+                },
+                "--unicode" => options.unicode = true,
+                _ => return Err(format!("Unknown option: {}", arg)),
+            }
+        }
+        if ply.is_some() {
+            match options.start {
+                Start::Pgn(_, ref mut plies) => *plies = ply,
+                _ => return Err("--ply only makes sense with --pgn".to_string()),
+            }
+        }
+        Ok(options)
+    }
+
+    // Set up the game to start from.
+    fn game(&self) -> Result<Game, String> {
+        match self.start {
+            Start::New => Ok(Game::new()),
+            Start::Fen(ref fen) => ForsythEdwardsNotation.parse_board(fen)
+                .map(Game::from_board)
+                .map_err(|error| format!("Invalid FEN: {}", error)),
+            Start::Pgn(ref path, plies) => {
+                let input = fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path, error))?;
+                let recorded = match PgnReader::new(&input).next() {
+                    Some(Ok(pgn))    => pgn.game,
+                    Some(Err(error)) => return Err(format!("Could not read {}: {}", path, error)),
+                    None             => return Err(format!("There is no game in {}", path)),
+                };
+                let plies = plies.unwrap_or(recorded.log.len());
+                if plies > recorded.log.len() {
+                    return Err(format!("The game in {} has only {} plies", path, recorded.log.len()));
+                }
+                let mut game = Game::from_board(recorded.start);
+                for entry in recorded.log[..plies].iter() {
+                    game.play(&entry.ply).map_err(|error| format!("Could not replay {}: {}", path, error))?;
+                }
+                Ok(game)
+            },
+        }
+    }
+
+    fn player(&self, color: Color) -> Player {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+}
+
+fn player(value: &str) -> Result<Player, String> {
+    match value {
+        "human"  => Ok(Player::Human),
+        "engine" => Ok(Player::Engine),
+        _        => Err(format!("Expected human or engine, not {}", value)),
+    }
+}
 
 
 fn main() {
+    if env::args().skip(1).any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error)  => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            process::exit(2);
+        },
+    };
+    let mut game = match options.game() {
+        Ok(game)   => game,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
     println!("This is Chess.");
-    let input_notation = &chess::notation::StandardAlgebraicNotation;
-    let output_notation = &chess::notation::DisplayBoardNotation::new(chess::notation::StandardAlgebraicNotation);
+    if options.unicode {
+        let mut output_notation = DisplayBoardNotation::new(UnicodeNotation);
+        output_notation.set_box_drawing(true);
+        play(&options, &mut game, output_notation);
+    } else {
+        play(&options, &mut game, DisplayBoardNotation::new(StandardAlgebraicNotation));
+    }
+    println!("Thanks for playing!");
+}
 
-    let mut game = &mut chess::Game::new();
-    println!("{}", game.unparse_board(output_notation));
+// Take turns until the game is over, or the player leaves.
+fn play<T: BoardOutputNotationHelper>(options: &Options, game: &mut Game, mut output_notation: DisplayBoardNotation<T>) {
+    let input_notation = options.notation.input();
+    let ply_notation = options.notation.output();
+    show(game, &output_notation);
 
     let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        let ply = game.parse_ply(input_notation, &line.unwrap());
-        match ply {
-            Some(ply) => game.play(&ply),
-            None      => panic!("Not a valid move."),
+    let mut lines = stdin.lock().lines();
+    loop {
+        if let Some(outcome) = game.outcome() {
+            println!("{}. {}", outcome, outcome.score());
+            break;
+        }
+
+        let color = game.board.color;
+        if options.player(color) == Player::Engine {
+            let result = engine::search(&game.board, &options.limits)
+                .expect("The engine found no move in a game that is not over.");
+            println!("{} plays {}", name(color), ply_notation.unparse_ply(&game.board, &result.best));
+            game.play(&result.best).expect("The engine chose an illegal move.");
+            show(game, &output_notation);
+            continue;
+        }
+
+        print!("{} to move: ", name(color));
+        io::stdout().flush().expect("Could not write to stdout.");
+        let line = match lines.next() {
+            Some(line) => line.expect("Could not read from stdin."),
+            None       => {
+                println!();
+                break;
+            },
+        };
+
+        match line.trim() {
+            ""      => (),
+            "help"  => println!("Type a move in {}, or one of:\n{}", options.notation.describe(), COMMANDS),
+            "quit"  => break,
+            "moves" => {
+                let moves: Vec<String> = game.board.legal_plies().iter()
+                    .map(|ply| ply_notation.unparse_ply(&game.board, ply))
+                    .collect();
+                println!("{}", moves.join(", "));
+            },
+            // Take back the engine's replies too, back to a human's turn.
+            "undo" => match game.undo() {
+                Some(_) => {
+                    while options.player(game.board.color) == Player::Engine && game.undo().is_some() {}
+                    show(game, &output_notation);
+                },
+                None => println!("There is nothing to undo."),
+            },
+            "flip" => {
+                output_notation.flip();
+                show(game, &output_notation);
+            },
+            "fen" => println!("{}", game.unparse_board(&ForsythEdwardsNotation)),
+            "pgn" => print!("{}", PgnGame::new(game.clone()).unparse_pgn()),
+            "draw" => match game.claimable_draw() {
+                Some(outcome) => {
+                    println!("{}. {}", outcome, outcome.score());
+                    break;
+                },
+                None => println!("There is no draw to claim."),
+            },
+            "resign" => {
+                let outcome = Outcome { winner: Some(game.board.color.other()), reason: Reason::Resignation };
+                println!("{}. {}", outcome, outcome.score());
+                break;
+            },
+            input => match game.parse_ply(input_notation, input) {
+                Some(ply) => match game.play(&ply) {
+                    Ok(())     => show(game, &output_notation),
+                    Err(error) => println!("{} is illegal: {}.", input, error),
+                },
+                None => match illegal(&game.board, input) {
+                    Some(error) => println!("{} is illegal: {}.", input, error),
+                    None        => println!("Could not read {} as a move. Type moves to list them, or help for more.", input),
+                },
+            },
         }
-        println!("{}", game.unparse_board(output_notation));
+    }
+}
+
+// Why the input may not be played, reading it as coordinates, as
+// castling, or as a SAN piece and destination. None if it can't be read
+// that way, or if it could be played after all.
+fn illegal(board: &Board, input: &str) -> Option<IllegalPly> {
+    if let Some(ply) = CoordinateNotation.parse_ply(board, input) {
+        return board.validate(&ply).err();
     }
 
-    println!("Thanks for playing!");
+    let home = match board.color { Color::White => 0, Color::Black => 7 };
+    let castle = |king: u8, rook: (u8, u8)| Ply::Castling(
+        Move { from: Location { rank: home, file: 4 }, to: Location { rank: home, file: king } },
+        Move { from: Location { rank: home, file: rook.0 }, to: Location { rank: home, file: rook.1 } },
+    );
+    let san = input.trim_end_matches(|chr: char| "+#!?".contains(chr));
+    match san {
+        "O-O" | "0-0"     => return board.validate(&castle(6, (7, 5))).err(),
+        "O-O-O" | "0-0-0" => return board.validate(&castle(2, (0, 3))).err(),
+        _ => (),
+    }
+
+    let san = san.split('=').next().unwrap_or(san).as_bytes();
+    if san.len() < 2 {
+        return None;
+    }
+    let to = location(san[san.len() - 2], san[san.len() - 1])?;
+    let rank = match san[0] {
+        b'K'        => Rank::King,
+        b'Q'        => Rank::Queen,
+        b'R'        => Rank::Rook,
+        b'B'        => Rank::Bishop,
+        b'N'        => Rank::Knight,
+        b'a'..=b'h' => Rank::Pawn,
+        _           => return None,
+    };
+    let capture = match *board.tile_at(&to) {
+        Tile::Empty    => None,
+        Tile::Taken(_) => Some(to),
+    };
+
+    // Of the pieces that might have been meant, prefer the reason of
+    // one that could move there but for its king.
+    let mut reason = None;
+    for from in (0..64).filter_map(|square| location(b'a' + square % 8, b'1' + square / 8)) {
+        let piece = Piece { rank, color: board.color };
+        if *board.tile_at(&from) != Tile::Taken(piece) {
+            continue;
+        }
+        let mv = Move { from, to };
+        let ply = if rank == Rank::Pawn && to.rank == 7 - home {
+            Ply::Promotion(mv, capture, Piece { rank: Rank::Queen, color: board.color })
+        } else {
+            Ply::Basic(mv, capture)
+        };
+        match board.validate(&ply) {
+            Ok(())                        => return None,
+            Err(IllegalPly::KingInCheck)  => reason = Some(IllegalPly::KingInCheck),
+            Err(error)                    => reason = reason.or(Some(error)),
+        }
+    }
+    reason
+}
+
+fn location(file: u8, rank: u8) -> Option<Location> {
+    match (file, rank) {
+        (b'a'..=b'h', b'1'..=b'8') => Some(Location { rank: rank - b'1', file: file - b'a' }),
+        _ => None,
+    }
+}
+
+fn name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
+
+// Draw the board, and warn of check and of draws to claim when the
+// game goes on.
+fn show(game: &Game, notation: &dyn BoardOutputNotation) {
+    println!("{}", game.unparse_board(notation));
+    if game.outcome().is_some() {
+        return;
+    }
+    if game.board.is_check() {
+        println!("Check!");
+    }
+    if let Some(outcome) = game.claimable_draw() {
+        println!("{} may be claimed; type draw to claim it.", outcome);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chess::{Board, IllegalPly, Location};
+    use chess::notation::{BoardInputNotation, BoardOutputNotation, ForsythEdwardsNotation};
+    use std::{env, fs};
+    use std::time::Duration;
+    use super::{illegal, Notation, Options, Player};
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    #[test]
+    fn illegal_reasons() {
+        let board = Board::new();
+        assert_eq!(illegal(&board, "Nf9"), None);
+        assert_eq!(illegal(&board, "hello"), None);
+        assert_eq!(illegal(&board, "e4"), None);
+        assert_eq!(illegal(&board, "Ke2"), Some(IllegalPly::InvalidMovement));
+        assert_eq!(illegal(&board, "e5"), Some(IllegalPly::InvalidMovement));
+        assert_eq!(illegal(&board, "O-O"), Some(IllegalPly::InvalidMovement));
+        assert_eq!(illegal(&board, "e2 e5"), Some(IllegalPly::InvalidMovement));
+        assert_eq!(illegal(&board, "e7e5"), Some(IllegalPly::WrongColor(Location { rank: 6, file: 4 })));
+        assert_eq!(illegal(&board, "e3 e4"), Some(IllegalPly::NoPiece(Location { rank: 2, file: 4 })));
+
+        let pinned = from_fen("4k3/4r3/8/8/8/8/4N3/4K2R w - - 0 1");
+        assert_eq!(illegal(&pinned, "Nc3"), Some(IllegalPly::KingInCheck));
+        assert_eq!(illegal(&pinned, "O-O"), Some(IllegalPly::CastlingForfeited));
+    }
+
+    #[test]
+    fn parse_players_and_strength() {
+        let options = parse(&[]).unwrap();
+        assert_eq!((options.white, options.black), (Player::Human, Player::Human));
+        assert_eq!((options.limits.depth, options.limits.time), (None, Some(Duration::from_secs(1))));
+
+        let options = parse(&["--white", "engine", "--black", "human", "--time", "2.5"]).unwrap();
+        assert_eq!((options.white, options.black), (Player::Engine, Player::Human));
+        assert_eq!(options.limits.time, Some(Duration::from_millis(2500)));
+
+        // A depth takes the place of the default time, unless a time is given too.
+        let options = parse(&["--black", "engine", "--depth", "4"]).unwrap();
+        assert_eq!((options.white, options.black), (Player::Human, Player::Engine));
+        assert_eq!((options.limits.depth, options.limits.time), (Some(4), None));
+        let options = parse(&["--depth", "4", "--time", "3"]).unwrap();
+        assert_eq!((options.limits.depth, options.limits.time), (Some(4), Some(Duration::from_secs(3))));
+
+        assert!(parse(&["--white", "robot"]).is_err());
+        assert!(parse(&["--black"]).is_err());
+        assert!(parse(&["--depth", "deep"]).is_err());
+        assert!(parse(&["--time", "soon"]).is_err());
+        assert!(parse(&["--colour", "white"]).is_err());
+    }
+
+    #[test]
+    fn parse_start_and_notation() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        let game = parse(&["--fen", fen]).unwrap().game().unwrap();
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&game.board), fen);
+        assert!(parse(&["--fen", "8/8/8 w"]).unwrap().game().is_err());
+
+        let path = env::temp_dir().join(format!("chessref-{}.pgn", std::process::id()));
+        fs::write(&path, "[Result \"*\"]\n\n1. e4 e5 2. Nf3 *\n").unwrap();
+        let path = path.to_str().unwrap();
+        let game = parse(&["--pgn", path]).unwrap().game().unwrap();
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&game.board),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+        let game = parse(&["--pgn", path, "--ply", "1"]).unwrap().game().unwrap();
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&game.board),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(parse(&["--pgn", path, "--ply", "4"]).unwrap().game().err(),
+            Some(format!("The game in {} has only 3 plies", path)));
+        fs::remove_file(path).unwrap();
+        assert_eq!(parse(&["--ply", "1"]).err(), Some("--ply only makes sense with --pgn".to_string()));
+        assert!(parse(&["--pgn", path, "--ply", "one"]).is_err());
+
+        assert_eq!(parse(&[]).unwrap().notation, Notation::San);
+        assert_eq!(parse(&["--notation", "coord"]).unwrap().notation, Notation::Coord);
+        assert_eq!(parse(&["--notation", "uci"]).unwrap().notation, Notation::Uci);
+        assert_eq!(parse(&["--notation", "lan"]).err(), Some("Expected san, coord or uci, not lan".to_string()));
+    }
 }
@@ -0,0 +1,48 @@
+extern crate chess;
+
+use std::env;
+use std::process;
+
+use chess::notation::{BoardInputNotation, ForsythEdwardsNotation, LongAlgebraicNotation, PlyOutputNotation};
+
+const USAGE: &str = "Usage: perft [divide] <depth> [<fen>]";
+
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let divide = args.first().is_some_and(|arg| arg == "divide");
+    if divide {
+        args.remove(0);
+    }
+
+    let depth = match args.first().and_then(|arg| arg.parse().ok()) {
+        Some(depth) => depth,
+        None        => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        },
+    };
+    let board = if args.len() > 1 {
+        match ForsythEdwardsNotation.parse_board(&args[1..].join(" ")) {
+            Ok(board)  => board,
+            Err(error) => {
+                eprintln!("Invalid FEN: {}", error);
+                process::exit(2);
+            },
+        }
+    } else {
+        chess::Board::new()
+    };
+
+    let nodes = if divide && depth > 0 {
+        let counts = chess::divide(&board, depth);
+        for &(ply, nodes) in counts.iter() {
+            println!("{}: {}", LongAlgebraicNotation.unparse_ply(&board, &ply), nodes);
+        }
+        println!();
+        counts.iter().map(|&(_, nodes)| nodes).sum()
+    } else {
+        chess::perft(&board, depth)
+    };
+    println!("Nodes: {}", nodes);
+}
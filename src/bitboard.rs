@@ -0,0 +1,494 @@
+use board::{Board, Tile, Castling};
+use color::Color;
+use piece::{Piece, Rank};
+use ply::{Location, Move, Ply};
+
+pub(crate) const RANKS: [Rank; 6] = [Rank::Pawn, Rank::Rook, Rank::Knight, Rank::Bishop, Rank::Queen, Rank::King];
+const PROMOTIONS: [Rank; 4] = [Rank::Queen, Rank::Rook, Rank::Bishop, Rank::Knight];
+
+// Rays run in these directions, as (ranks, files). The first four
+// move towards higher square numbers, the last four towards lower.
+const DIRECTIONS: [(i8, i8); 8] = [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1), (-1, 1), (-1, -1)];
+const ROOK_RAYS: [usize; 4] = [0, 1, 4, 5];
+const BISHOP_RAYS: [usize; 4] = [2, 3, 6, 7];
+
+const KNIGHT_ATTACKS: [u64; 64] = step_table(&[
+    (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2),
+]);
+const KING_ATTACKS: [u64; 64] = step_table(&[
+    (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1),
+]);
+const PAWN_ATTACKS: [[u64; 64]; 2] = [step_table(&[(1, -1), (1, 1)]), step_table(&[(-1, -1), (-1, 1)])];
+const RAYS: [[u64; 64]; 8] = ray_tables();
+
+// The tiles reached by one step of each offset from every square.
+const fn step_table(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut index = 0;
+        while index < offsets.len() {
+            let rank = (square / 8) as i8 + offsets[index].0;
+            let file = (square % 8) as i8 + offsets[index].1;
+            if rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+                table[square] |= 1 << (rank * 8 + file);
+            }
+            index += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+// The tiles from every square to the edge of the board, in each direction.
+const fn ray_tables() -> [[u64; 64]; 8] {
+    let mut tables = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (ranks, files) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let mut rank = (square / 8) as i8 + ranks;
+            let mut file = (square % 8) as i8 + files;
+            while rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+                tables[direction][square] |= 1 << (rank * 8 + file);
+                rank += ranks;
+                file += files;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    tables
+}
+
+// The tiles along a ray up to and including the first occupied one.
+fn ray_attacks(square: u8, occupied: u64, direction: usize) -> u64 {
+    let ray = RAYS[direction][square as usize];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let blocker = if direction < 4 {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][blocker as usize]
+}
+
+/// The tiles a knight on the square attacks.
+pub fn knight_attacks(square: u8) -> u64 {
+    KNIGHT_ATTACKS[square as usize]
+}
+
+/// The tiles a king on the square attacks.
+pub fn king_attacks(square: u8) -> u64 {
+    KING_ATTACKS[square as usize]
+}
+
+/// The tiles a pawn of the color on the square attacks.
+pub fn pawn_attacks(color: Color, square: u8) -> u64 {
+    PAWN_ATTACKS[color as usize][square as usize]
+}
+
+/// The tiles a rook on the square attacks, given the occupied tiles.
+pub fn rook_attacks(square: u8, occupied: u64) -> u64 {
+    ROOK_RAYS.iter().fold(0, |attacks, &direction| attacks | ray_attacks(square, occupied, direction))
+}
+
+/// The tiles a bishop on the square attacks, given the occupied tiles.
+pub fn bishop_attacks(square: u8, occupied: u64) -> u64 {
+    BISHOP_RAYS.iter().fold(0, |attacks, &direction| attacks | ray_attacks(square, occupied, direction))
+}
+
+/// The tiles a queen on the square attacks, given the occupied tiles.
+pub fn queen_attacks(square: u8, occupied: u64) -> u64 {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+fn square(location: Location) -> u8 {
+    location.rank * 8 + location.file
+}
+
+fn location(square: u8) -> Location {
+    Location { rank: square / 8, file: square % 8 }
+}
+
+// The square numbers of the set bits, lowest first.
+fn squares(mut set: u64) -> impl Iterator<Item = u8> {
+    std::iter::from_fn(move || {
+        if set == 0 {
+            return None;
+        }
+        let square = set.trailing_zeros() as u8;
+        set &= set - 1;
+        Some(square)
+    })
+}
+
+/// A board represented as a set of 64-bit masks, one for each kind
+/// of piece of each color. Bit `rank * 8 + file` stands for the tile
+/// at that location. It converts losslessly to and from a Board,
+/// and answers attack and legal move queries much faster.
+///
+/// https://www.chessprogramming.org/Bitboards
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BitBoard {
+    /// Indexed by color, then by rank, in the order they are declared.
+    pub pieces: [[u64; 6]; 2],
+    pub color: Color,
+    pub castling: Castling,
+    pub enpassant: Option<Location>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl From<Board> for BitBoard {
+    fn from(board: Board) -> BitBoard {
+        let mut pieces = [[0; 6]; 2];
+        for (rank, row) in board.grid.iter().enumerate() {
+            for (file, tile) in row.iter().enumerate() {
+                if let Tile::Taken(piece) = *tile {
+                    pieces[piece.color as usize][piece.rank as usize] |= 1 << (rank * 8 + file);
+                }
+            }
+        }
+        BitBoard {
+            pieces,
+            color: board.color,
+            castling: board.castling,
+            enpassant: board.enpassant,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+        }
+    }
+}
+
+impl From<BitBoard> for Board {
+    fn from(bitboard: BitBoard) -> Board {
+        let mut grid = [[Tile::Empty; 8]; 8];
+        for square in 0..64u8 {
+            if let Some(piece) = bitboard.piece_at(square) {
+                grid[(square / 8) as usize][(square % 8) as usize] = Tile::Taken(piece);
+            }
+        }
+        Board {
+            grid,
+            color: bitboard.color,
+            castling: bitboard.castling,
+            enpassant: bitboard.enpassant,
+            halfmove_clock: bitboard.halfmove_clock,
+            fullmove_number: bitboard.fullmove_number,
+        }
+    }
+}
+
+impl BitBoard {
+    /// The tiles occupied by pieces of the color.
+    pub fn occupied_by(&self, color: Color) -> u64 {
+        self.pieces[color as usize].iter().fold(0, |set, pieces| set | pieces)
+    }
+
+    /// The tiles occupied by any piece.
+    pub fn occupied(&self) -> u64 {
+        self.occupied_by(Color::White) | self.occupied_by(Color::Black)
+    }
+
+    /// The piece on the square, if there is one.
+    pub fn piece_at(&self, square: u8) -> Option<Piece> {
+        for &color in [Color::White, Color::Black].iter() {
+            for &rank in RANKS.iter() {
+                if self.pieces[color as usize][rank as usize] & (1 << square) != 0 {
+                    return Some(Piece { rank, color });
+                }
+            }
+        }
+        None
+    }
+
+    /// The pieces of the given color that attack the square.
+    pub fn attackers(&self, square: u8, by: Color) -> u64 {
+        let occupied = self.occupied();
+        let pieces = &self.pieces[by as usize];
+        let queens = pieces[Rank::Queen as usize];
+        (pawn_attacks(by.other(), square) & pieces[Rank::Pawn as usize])
+            | (knight_attacks(square) & pieces[Rank::Knight as usize])
+            | (king_attacks(square) & pieces[Rank::King as usize])
+            | (rook_attacks(square, occupied) & (pieces[Rank::Rook as usize] | queens))
+            | (bishop_attacks(square, occupied) & (pieces[Rank::Bishop as usize] | queens))
+    }
+
+    /// Whether any piece of the given color attacks the square.
+    pub fn is_attacked(&self, square: u8, by: Color) -> bool {
+        self.attackers(square, by) != 0
+    }
+
+    /// Whether the king of the side to move is under attack.
+    pub fn is_check(&self) -> bool {
+        let king = self.pieces[self.color as usize][Rank::King as usize];
+        king != 0 && self.is_attacked(king.trailing_zeros() as u8, self.color.other())
+    }
+
+    /// All of the plies that the side to move may legally play.
+    pub fn legal_plies(&self) -> Vec<Ply> {
+        let mut plies = Vec::new();
+        self.pseudo_legal_plies(&mut plies);
+        plies.retain(|ply| {
+            let mut after = *self;
+            after.play(ply);
+            let king = after.pieces[self.color as usize][Rank::King as usize];
+            king == 0 || !after.is_attacked(king.trailing_zeros() as u8, self.color.other())
+        });
+        plies
+    }
+
+    fn pseudo_legal_plies(&self, plies: &mut Vec<Ply>) {
+        let color = self.color;
+        let pieces = &self.pieces[color as usize];
+        let own = self.occupied_by(color);
+        let theirs = self.occupied_by(color.other());
+        let occupied = own | theirs;
+
+        let basic = |plies: &mut Vec<Ply>, from: u8, targets: u64| {
+            for to in squares(targets & !own) {
+                let capture = if theirs & (1 << to) != 0 { Some(location(to)) } else { None };
+                plies.push(Ply::Basic(Move { from: location(from), to: location(to) }, capture));
+            }
+        };
+        for from in squares(pieces[Rank::Knight as usize]) {
+            basic(plies, from, knight_attacks(from));
+        }
+        for from in squares(pieces[Rank::Bishop as usize]) {
+            basic(plies, from, bishop_attacks(from, occupied));
+        }
+        for from in squares(pieces[Rank::Rook as usize]) {
+            basic(plies, from, rook_attacks(from, occupied));
+        }
+        for from in squares(pieces[Rank::Queen as usize]) {
+            basic(plies, from, queen_attacks(from, occupied));
+        }
+        for from in squares(pieces[Rank::King as usize]) {
+            basic(plies, from, king_attacks(from));
+            self.castling_plies(from, occupied, plies);
+        }
+
+        let (forward, start, last): (i8, u8, u8) = match color {
+            Color::White => (8, 1, 7),
+            Color::Black => (-8, 6, 0),
+        };
+        let enpassant = self.enpassant.map_or(0, |target| 1 << square(target));
+        for from in squares(pieces[Rank::Pawn as usize]) {
+            let mut targets = Vec::new();
+            // A pawn that has somehow reached the last rank has nowhere
+            // left to push to.
+            let ahead = from as i8 + forward;
+            if (0..64).contains(&ahead) && occupied & (1 << ahead) == 0 {
+                targets.push((ahead as u8, None));
+                let double = ahead + forward;
+                if from / 8 == start && (0..64).contains(&double) && occupied & (1 << double) == 0 {
+                    plies.push(Ply::Basic(Move { from: location(from), to: location(double as u8) }, None));
+                }
+            }
+            for to in squares(pawn_attacks(color, from) & theirs) {
+                targets.push((to, Some(location(to))));
+            }
+            for to in squares(pawn_attacks(color, from) & enpassant) {
+                let captured = Location { rank: from / 8, file: to % 8 };
+                plies.push(Ply::EnPassant(Move { from: location(from), to: location(to) }, captured));
+            }
+
+            for (to, capture) in targets {
+                let mv = Move { from: location(from), to: location(to) };
+                if to / 8 == last {
+                    for &rank in PROMOTIONS.iter() {
+                        plies.push(Ply::Promotion(mv, capture, Piece { rank, color }));
+                    }
+                } else {
+                    plies.push(Ply::Basic(mv, capture));
+                }
+            }
+        }
+    }
+
+    fn castling_plies(&self, from: u8, occupied: u64, plies: &mut Vec<Ply>) {
+        let (home, king_side, queen_side) = match self.color {
+            Color::White => (0, self.castling.white_king, self.castling.white_queen),
+            Color::Black => (56, self.castling.black_king, self.castling.black_queen),
+        };
+        let other = self.color.other();
+        if from != home + 4 || self.is_attacked(from, other) {
+            return;
+        }
+
+        // (allowed, rook origin, king destination, rook destination, tiles that must be empty)
+        let sides = [
+            (king_side, home + 7, home + 6, home + 5, 0b0110_0000u64 << home),
+            (queen_side, home, home + 2, home + 3, 0b0000_1110u64 << home),
+        ];
+        let rooks = self.pieces[self.color as usize][Rank::Rook as usize];
+        for &(allowed, rook_from, king_to, rook_to, between) in sides.iter() {
+            if allowed && rooks & (1 << rook_from) != 0 && occupied & between == 0
+                && !self.is_attacked(rook_to, other) {
+                plies.push(Ply::Castling(
+                    Move { from: location(from), to: location(king_to) },
+                    Move { from: location(rook_from), to: location(rook_to) },
+                ));
+            }
+        }
+    }
+
+    fn take(&mut self, square: u8) -> Option<Piece> {
+        let piece = self.piece_at(square)?;
+        self.pieces[piece.color as usize][piece.rank as usize] &= !(1 << square);
+        Some(piece)
+    }
+
+    fn put(&mut self, square: u8, piece: Piece) {
+        self.pieces[piece.color as usize][piece.rank as usize] |= 1 << square;
+    }
+
+    /// Play the ply without checking that it is legal, updating the
+    /// castling rights, en passant target and clocks as Game does.
+    pub fn play(&mut self, ply: &Ply) {
+        let mv = ply.movement();
+        let (from, to) = (square(mv.from), square(mv.to));
+        let capture = match *ply {
+            Ply::Basic(_, capture) | Ply::Promotion(_, capture, _) => capture,
+            Ply::EnPassant(_, capture) => Some(capture),
+            Ply::Castling(..) => None,
+        };
+        if let Some(capture) = capture {
+            self.take(square(capture));
+        }
+        let moved = match self.take(from) {
+            Some(piece) => piece,
+            None        => return,
+        };
+        match *ply {
+            Ply::Promotion(_, _, piece) => self.put(to, piece),
+            _ => self.put(to, moved),
+        }
+        if let Ply::Castling(_, rook) = *ply {
+            if let Some(piece) = self.take(square(rook.from)) {
+                self.put(square(rook.to), piece);
+            }
+        }
+
+        // Anything leaving or arriving on a king or rook's home tile
+        // means that piece has moved or been captured.
+        for &square in [from, to].iter() {
+            match square {
+                0  => self.castling.white_queen = false,
+                7  => self.castling.white_king = false,
+                4  => {
+                    self.castling.white_king = false;
+                    self.castling.white_queen = false;
+                },
+                56 => self.castling.black_queen = false,
+                63 => self.castling.black_king = false,
+                60 => {
+                    self.castling.black_king = false;
+                    self.castling.black_queen = false;
+                },
+                _  => (),
+            }
+        }
+
+        let pawn = moved.rank == Rank::Pawn;
+        self.enpassant = if pawn && (from as i8 - to as i8).abs() == 16 {
+            Some(location((from + to) / 2))
+        } else {
+            None
+        };
+        if pawn || capture.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if self.color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.color = self.color.other();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use color::Color;
+    use game::Game;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation};
+    use super::{BitBoard, knight_attacks, rook_attacks, bishop_attacks};
+
+    const POSITIONS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ];
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    #[test]
+    fn attack_tables() {
+        assert_eq!(knight_attacks(0), (1 << 10) | (1 << 17));
+        assert_eq!(rook_attacks(0, 0).count_ones(), 14);
+        assert_eq!(rook_attacks(0, 1 << 16), (1 << 8) | (1 << 16) | 0xfe);
+        assert_eq!(bishop_attacks(27, 0).count_ones(), 13);
+        assert_eq!(bishop_attacks(0, 1 << 18), (1 << 9) | (1 << 18));
+    }
+
+    #[test]
+    fn conversion() {
+        for fen in POSITIONS.iter() {
+            let board = from_fen(fen);
+            let bitboard = BitBoard::from(board);
+            assert!(Board::from(bitboard) == board);
+        }
+        let bitboard = BitBoard::from(Board::new());
+        assert_eq!(bitboard.occupied_by(Color::White), 0xffff);
+        assert_eq!(bitboard.occupied(), 0xffff_0000_0000_ffff);
+    }
+
+    #[test]
+    fn legal_plies_match_board() {
+        for fen in POSITIONS.iter() {
+            let board = from_fen(fen);
+            let bitboard = BitBoard::from(board);
+            let expected = board.legal_plies();
+            let plies = bitboard.legal_plies();
+            assert_eq!(plies.len(), expected.len(), "{}", fen);
+            for ply in plies.iter() {
+                assert!(expected.contains(ply), "{} {:?}", fen, ply);
+            }
+        }
+    }
+
+    #[test]
+    fn pawns_on_last_rank() {
+        for &fen in ["P3k3/8/8/8/8/8/8/4K3 w - - 0 1", "4k3/8/8/8/8/8/8/p3K3 b - - 0 1"].iter() {
+            let plies = BitBoard::from(from_fen(fen)).legal_plies();
+            assert_eq!(plies.len(), 5, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn play_matches_game() {
+        for fen in POSITIONS.iter() {
+            let board = from_fen(fen);
+            for ply in board.legal_plies() {
+                let mut game = Game::from_board(board);
+                game.play(&ply).unwrap();
+                let mut bitboard = BitBoard::from(board);
+                bitboard.play(&ply);
+                assert!(Board::from(bitboard) == game.board, "{} {:?}", fen, ply);
+                assert_eq!(bitboard.is_check(), game.board.is_check());
+            }
+        }
+    }
+}
@@ -33,6 +33,12 @@ pub struct Board {
 
 }
 
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
+    }
+}
+
 impl Board {
     pub fn new() -> Board {
         Board {
@@ -85,7 +91,10 @@ impl Board {
 }
 
 
+// These tests compare against None and clone Copy boards on purpose;
+// keep them as they were written.
 #[cfg(test)]
+#[allow(clippy::partialeq_to_none, clippy::clone_on_copy)]
 mod tests {
     use super::{Board, Tile, Castling};
     use piece::{Piece, Rank};
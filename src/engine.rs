@@ -0,0 +1,363 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use bitboard::BitBoard;
+use board::Board;
+use eval::{evaluate_bitboard, value};
+use piece::Rank;
+use ply::{Location, Ply};
+
+/// The score for delivering checkmate right away. Mates further off
+/// score one less for every ply it takes to get there.
+pub const MATE: i32 = 100_000;
+/// The deepest the search will ever go.
+pub const MAX_DEPTH: u32 = 64;
+
+const INFINITY: i32 = MATE + 1;
+
+/// When to stop searching. The search stops at whichever limit it
+/// reaches first; with none set it goes on to MAX_DEPTH.
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    /// How many plies deep to search
+    pub depth: Option<u32>,
+    /// How many positions to visit
+    pub nodes: Option<u64>,
+    /// How long to search for
+    pub time: Option<Duration>,
+    /// Set from another thread to stop the search early
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+/// What the search settled on.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SearchResult {
+    /// The ply to play
+    pub best: Ply,
+    /// In centipawns for the side to move, or MATE less the number
+    /// of plies to a forced mate, negated if it is the side to move
+    /// that is getting mated.
+    pub score: i32,
+    /// The line of play expected to follow, starting with the best ply
+    pub pv: Vec<Ply>,
+    /// How many positions were visited
+    pub nodes: u64,
+    /// The depth of the last search that ran to completion
+    pub depth: u32,
+}
+
+/// Look for the best ply for the side to move, using iterative
+/// deepening alpha-beta search. Returns None if there are no legal
+/// plies. The first iteration always runs to completion, so there is
+/// a ply to play however tight the limits are.
+///
+/// https://www.chessprogramming.org/Negamax
+pub fn search(board: &Board, limits: &Limits) -> Option<SearchResult> {
+    search_with(board, limits, |_| ())
+}
+
+/// Search as with `search`, passing the result of each iteration to
+/// the callback as soon as it completes.
+pub fn search_with<F>(board: &Board, limits: &Limits, mut report: F) -> Option<SearchResult>
+    where F: FnMut(&SearchResult)
+{
+    let root = BitBoard::from(*board);
+    if root.legal_plies().is_empty() {
+        return None;
+    }
+
+    let mut searcher = Searcher {
+        limits,
+        start: Instant::now(),
+        nodes: 0,
+        depth: 0,
+        stopped: false,
+        previous: Vec::new(),
+        killers: vec![[None; 2]; MAX_DEPTH as usize + 1],
+        history: [[[0; 64]; 64]; 2],
+    };
+    let mut result = None;
+    let max_depth = limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+    for depth in 1..=max_depth {
+        searcher.depth = depth;
+        let mut pv = Vec::new();
+        let score = searcher.negamax(&root, depth, -INFINITY, INFINITY, 0, &mut pv);
+        if searcher.stopped || pv.is_empty() {
+            break;
+        }
+        let iteration = SearchResult { best: pv[0], score, pv: pv.clone(), nodes: searcher.nodes, depth };
+        report(&iteration);
+        result = Some(iteration);
+        searcher.previous = pv;
+        if score.abs() > MATE - MAX_DEPTH as i32 || searcher.limit_reached() {
+            break;
+        }
+    }
+    result.map(|result| SearchResult { nodes: searcher.nodes, ..result })
+}
+
+struct Searcher<'a> {
+    limits: &'a Limits,
+    start: Instant,
+    nodes: u64,
+    /// The depth of the current iteration
+    depth: u32,
+    stopped: bool,
+    /// The principal variation from the last iteration
+    previous: Vec<Ply>,
+    /// Quiet plies that caused a cutoff, by distance from the root
+    killers: Vec<[Option<Ply>; 2]>,
+    /// How often quiet plies caused a cutoff, by color, origin and destination
+    history: [[[u32; 64]; 64]; 2],
+}
+
+impl<'a> Searcher<'a> {
+    fn limit_reached(&self) -> bool {
+        self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || self.limits.time.is_some_and(|time| self.start.elapsed() >= time)
+            || self.limits.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+
+    // Only look at the clock now and then, and never during the first
+    // iteration.
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.depth > 1 && self.nodes.is_multiple_of(1024) {
+            self.stopped = self.limit_reached();
+        }
+        self.stopped
+    }
+
+    fn negamax(&mut self, board: &BitBoard, depth: u32, mut alpha: i32, beta: i32, height: usize, pv: &mut Vec<Ply>) -> i32 {
+        pv.clear();
+        if depth == 0 || height >= MAX_DEPTH as usize {
+            return self.quiescence(board, alpha, beta);
+        }
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let mut plies = board.legal_plies();
+        if plies.is_empty() {
+            return if board.is_check() { height as i32 - MATE } else { 0 };
+        }
+        // Below the root, a draw that can be claimed is as good as one.
+        if height > 0 && board.halfmove_clock >= 100 {
+            return 0;
+        }
+        self.order(board, &mut plies, height);
+
+        let mut best = -INFINITY;
+        let mut line = Vec::new();
+        for ply in plies {
+            let mut after = *board;
+            after.play(&ply);
+            let score = -self.negamax(&after, depth - 1, -beta, -alpha, height + 1, &mut line);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(ply);
+                pv.extend_from_slice(&line);
+            }
+            if alpha >= beta {
+                if victim(board, &ply).is_none() {
+                    let killers = &mut self.killers[height];
+                    if killers[0] != Some(ply) {
+                        killers[1] = killers[0];
+                        killers[0] = Some(ply);
+                    }
+                    let mv = ply.movement();
+                    self.history[board.color as usize][square(mv.from)][square(mv.to)] += depth * depth;
+                }
+                break;
+            }
+        }
+        best
+    }
+
+    // Search only captures, until the position is quiet enough to
+    // trust the static evaluation.
+    // https://www.chessprogramming.org/Quiescence_Search
+    fn quiescence(&mut self, board: &BitBoard, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let stand_pat = evaluate_bitboard(board);
+        if stand_pat >= beta {
+            return stand_pat;
+        }
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        let mut captures: Vec<(i32, Ply)> = board.legal_plies().into_iter()
+            .filter_map(|ply| victim(board, &ply).map(|victim| (mvv_lva(board, &ply, victim), ply)))
+            .collect();
+        captures.sort_by_key(|&(key, _)| -key);
+
+        let mut best = stand_pat;
+        for (_, ply) in captures {
+            let mut after = *board;
+            after.play(&ply);
+            let score = -self.quiescence(&after, -beta, -alpha);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+
+    // Try the most promising plies first, so that cutoffs come early:
+    // the last principal variation, then captures of the most valuable
+    // victims by the least valuable attackers, then killers, then
+    // quiet plies by their history.
+    fn order(&self, board: &BitBoard, plies: &mut [Ply], height: usize) {
+        let previous = self.previous.get(height).copied();
+        let killers = self.killers[height];
+        plies.sort_by_cached_key(|ply| {
+            let mv = ply.movement();
+            let key = if previous == Some(*ply) {
+                1_000_000
+            } else if let Some(victim) = victim(board, ply) {
+                100_000 + mvv_lva(board, ply, victim)
+            } else if let Ply::Promotion(..) = *ply {
+                90_000
+            } else if killers[0] == Some(*ply) {
+                80_000
+            } else if killers[1] == Some(*ply) {
+                79_000
+            } else {
+                self.history[board.color as usize][square(mv.from)][square(mv.to)].min(70_000) as i32
+            };
+            -key
+        });
+    }
+}
+
+fn square(location: Location) -> usize {
+    location.rank as usize * 8 + location.file as usize
+}
+
+// The rank of the piece the ply captures, if any.
+fn victim(board: &BitBoard, ply: &Ply) -> Option<Rank> {
+    match *ply {
+        Ply::Basic(_, capture) | Ply::Promotion(_, capture, _) => capture,
+        Ply::EnPassant(_, capture) => Some(capture),
+        Ply::Castling(..) => None,
+    }.and_then(|capture| board.piece_at(square(capture) as u8)).map(|piece| piece.rank)
+}
+
+// Most valuable victim, least valuable attacker.
+// https://www.chessprogramming.org/MVV-LVA
+fn mvv_lva(board: &BitBoard, ply: &Ply, victim: Rank) -> i32 {
+    let attacker = board.piece_at(square(ply.movement().from) as u8).map_or(0, |piece| value(piece.rank));
+    value(victim) * 10 - attacker / 10
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+    use board::Board;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation, LongAlgebraicNotation, PlyOutputNotation};
+    use super::{search, search_with, Limits, MATE};
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    fn depth(depth: u32) -> Limits {
+        Limits { depth: Some(depth), ..Limits::default() }
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let board = from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let result = search(&board, &depth(3)).unwrap();
+        assert_eq!(LongAlgebraicNotation.unparse_ply(&board, &result.best), "a1a8");
+        assert_eq!(result.score, MATE - 1);
+        assert_eq!(result.pv, vec![result.best]);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let board = from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+        let result = search(&board, &depth(4)).unwrap();
+        assert_eq!(result.score, MATE - 3);
+        assert_eq!(result.pv.len(), 3);
+    }
+
+    #[test]
+    fn wins_material() {
+        let board = from_fen("4k3/8/8/3q4/8/8/3R4/3RK3 w - - 0 1");
+        let result = search(&board, &depth(2)).unwrap();
+        assert_eq!(LongAlgebraicNotation.unparse_ply(&board, &result.best), "d2d5");
+        assert!(result.score > 500);
+    }
+
+    #[test]
+    fn avoids_hanging_piece() {
+        // Taking the pawn would lose the queen to the knight.
+        let board = from_fen("4k3/8/5n2/3p4/8/8/8/3QK3 w - - 0 1");
+        let result = search(&board, &depth(3)).unwrap();
+        assert!(LongAlgebraicNotation.unparse_ply(&board, &result.best) != "d1d5");
+    }
+
+    #[test]
+    fn limits() {
+        let board = Board::new();
+        let result = search(&board, &Limits { nodes: Some(2000), ..Limits::default() }).unwrap();
+        assert!(result.depth >= 1);
+        assert!(result.nodes < 4000);
+
+        let result = search(&board, &Limits { time: Some(Duration::from_millis(50)), ..Limits::default() }).unwrap();
+        assert!(result.depth >= 1);
+        assert!(board.legal_plies().contains(&result.best));
+
+        let stop = Arc::new(AtomicBool::new(true));
+        let result = search(&board, &Limits { stop: Some(stop), ..Limits::default() }).unwrap();
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn reports_iterations() {
+        let mut depths = Vec::new();
+        let result = search_with(&Board::new(), &depth(3), |result| depths.push(result.depth)).unwrap();
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert_eq!(result.depth, 3);
+    }
+
+    #[test]
+    fn fifty_move_clock_at_root() {
+        let board = from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 100 80");
+        let result = search(&board, &depth(3)).unwrap();
+        assert!(board.legal_plies().contains(&result.best));
+        assert!(!result.pv.is_empty());
+    }
+
+    #[test]
+    fn no_legal_plies() {
+        let board = from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(search(&board, &depth(3)), None);
+    }
+}
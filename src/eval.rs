@@ -0,0 +1,324 @@
+use bitboard::{BitBoard, RANKS};
+use board::Board;
+use color::Color;
+use piece::Rank;
+
+/// The phase of a game with all of its pieces on the board. Knights
+/// and bishops count one each, rooks two and queens four.
+const OPENING: i32 = 24;
+
+const DOUBLED: i32 = -15;
+const ISOLATED: i32 = -15;
+// By how many ranks the pawn has advanced.
+const PASSED_MIDDLE: [i32; 8] = [0, 5, 5, 10, 15, 25, 40, 0];
+const PASSED_END: [i32; 8] = [0, 10, 15, 25, 45, 75, 120, 0];
+const SHIELD: i32 = 12;
+const OPEN_FILE: i32 = -25;
+
+// Piece-square tables, as seen from White's side, with the eighth
+// rank first. Only pawns and kings are told apart by game phase.
+// https://www.chessprogramming.org/Simplified_Evaluation_Function
+const PAWN_MIDDLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+const PAWN_END: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    80, 80, 80, 80, 80, 80, 80, 80,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    30, 30, 30, 30, 30, 30, 30, 30,
+    20, 20, 20, 20, 20, 20, 20, 20,
+    10, 10, 10, 10, 10, 10, 10, 10,
+     0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+const KNIGHT: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+const BISHOP: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+const ROOK: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+const QUEEN: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+const KING_MIDDLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+const KING_END: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+/// The worth of a piece of the rank, in centipawns. The king has no
+/// material value, since it can never be traded.
+pub fn value(rank: Rank) -> i32 {
+    match rank {
+        Rank::Pawn   => 100,
+        Rank::Knight => 320,
+        Rank::Bishop => 330,
+        Rank::Rook   => 500,
+        Rank::Queen  => 900,
+        Rank::King   => 0,
+    }
+}
+
+/// The terms that make up an evaluation, each in centipawns for the
+/// side to move.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Evaluation {
+    /// The value of the pieces on the board
+    pub material: i32,
+    /// How well placed the pieces are
+    pub placement: i32,
+    /// Doubled, isolated and passed pawns
+    pub pawn_structure: i32,
+    /// How well the king is sheltered by its pawns
+    pub king_safety: i32,
+    /// How far from the endgame the position is, from OPENING
+    /// with all pieces on the board down to zero
+    pub phase: i32,
+}
+
+impl Evaluation {
+    /// The score for the side to move, in centipawns.
+    pub fn total(&self) -> i32 {
+        self.material + self.placement + self.pawn_structure + self.king_safety
+    }
+}
+
+/// Score the position for the side to move, in centipawns, without
+/// searching any plies.
+pub fn evaluate(board: &Board) -> i32 {
+    breakdown(board).total()
+}
+
+/// The terms that make up the score of the position.
+pub fn breakdown(board: &Board) -> Evaluation {
+    evaluate_terms(&BitBoard::from(*board))
+}
+
+pub(crate) fn evaluate_bitboard(board: &BitBoard) -> i32 {
+    evaluate_terms(board).total()
+}
+
+fn evaluate_terms(board: &BitBoard) -> Evaluation {
+    let phase = phase(board);
+    let (white, black) = (side_terms(board, Color::White, phase), side_terms(board, Color::Black, phase));
+    let sign = if board.color == Color::White { 1 } else { -1 };
+    Evaluation {
+        material: sign * (white.material - black.material),
+        placement: sign * (white.placement - black.placement),
+        pawn_structure: sign * (white.pawn_structure - black.pawn_structure),
+        king_safety: sign * (white.king_safety - black.king_safety),
+        phase,
+    }
+}
+
+fn phase(board: &BitBoard) -> i32 {
+    let count = |rank: Rank| (board.pieces[0][rank as usize] | board.pieces[1][rank as usize]).count_ones() as i32;
+    let phase = count(Rank::Knight) + count(Rank::Bishop) + 2 * count(Rank::Rook) + 4 * count(Rank::Queen);
+    phase.min(OPENING)
+}
+
+// Blend the middlegame and endgame scores by the phase.
+fn taper(middle: i32, end: i32, phase: i32) -> i32 {
+    (middle * phase + end * (OPENING - phase)) / OPENING
+}
+
+fn file_mask(file: i8) -> u64 {
+    if (0..8).contains(&file) { 0x0101_0101_0101_0101 << file } else { 0 }
+}
+
+// The squares in front of the square, from the color's side.
+fn ahead(color: Color, square: u32) -> u64 {
+    let rank = square / 8;
+    match color {
+        Color::White if rank < 7 => !0 << ((rank + 1) * 8),
+        Color::Black if rank > 0 => !0 >> ((8 - rank) * 8),
+        _ => 0,
+    }
+}
+
+// How many ranks the square is from the color's home rank.
+fn relative_rank(color: Color, square: u32) -> usize {
+    match color {
+        Color::White => (square / 8) as usize,
+        Color::Black => 7 - (square / 8) as usize,
+    }
+}
+
+// The terms for one color alone, from its own side.
+fn side_terms(board: &BitBoard, color: Color, phase: i32) -> Evaluation {
+    let pieces = &board.pieces[color as usize];
+    let mut terms = Evaluation::default();
+
+    for (index, &rank) in RANKS.iter().enumerate() {
+        let mut set = pieces[index];
+        while set != 0 {
+            let square = set.trailing_zeros();
+            set &= set - 1;
+            // The tables have the eighth rank first, as White sees them.
+            let entry = match color {
+                Color::White => (7 - square / 8) * 8 + square % 8,
+                Color::Black => square,
+            } as usize;
+            terms.material += value(rank);
+            terms.placement += match rank {
+                Rank::Pawn   => taper(PAWN_MIDDLE[entry], PAWN_END[entry], phase),
+                Rank::Knight => KNIGHT[entry],
+                Rank::Bishop => BISHOP[entry],
+                Rank::Rook   => ROOK[entry],
+                Rank::Queen  => QUEEN[entry],
+                Rank::King   => taper(KING_MIDDLE[entry], KING_END[entry], phase),
+            };
+        }
+    }
+
+    let pawns = pieces[Rank::Pawn as usize];
+    let theirs = board.pieces[color.other() as usize][Rank::Pawn as usize];
+    let mut set = pawns;
+    while set != 0 {
+        let square = set.trailing_zeros();
+        set &= set - 1;
+        let file = (square % 8) as i8;
+        let neighbours = file_mask(file - 1) | file_mask(file + 1);
+        let ahead = ahead(color, square);
+        if pawns & file_mask(file) & ahead != 0 {
+            terms.pawn_structure += DOUBLED;
+        }
+        if pawns & neighbours == 0 {
+            terms.pawn_structure += ISOLATED;
+        }
+        if theirs & (neighbours | file_mask(file)) & ahead == 0 {
+            let rank = relative_rank(color, square);
+            terms.pawn_structure += taper(PASSED_MIDDLE[rank], PASSED_END[rank], phase);
+        }
+    }
+
+    // Pawns just in front of the king shelter it, and open files next
+    // to it let the rooks and queens in. Both matter less as the
+    // pieces come off.
+    let king = pieces[Rank::King as usize];
+    if king != 0 {
+        let square = king.trailing_zeros();
+        let file = (square % 8) as i8;
+        let near = match color {
+            Color::White => ahead(color, square) & !ahead(color, square + 16),
+            Color::Black => ahead(color, square) & !ahead(color, square.saturating_sub(16)),
+        };
+        let mut safety = 0;
+        for file in file - 1..=file + 1 {
+            if file_mask(file) == 0 {
+                continue;
+            }
+            safety += SHIELD * (pawns & file_mask(file) & near).count_ones().min(1) as i32;
+            if pawns & file_mask(file) == 0 {
+                safety += OPEN_FILE;
+            }
+        }
+        terms.king_safety = taper(safety, 0, phase);
+    }
+
+    terms
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation};
+    use super::{evaluate, breakdown};
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    #[test]
+    fn symmetric() {
+        let board = Board::new();
+        assert_eq!(evaluate(&board), 0);
+        assert_eq!(breakdown(&board).phase, 24);
+
+        let white = from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let black = from_fen("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3");
+        assert_eq!(evaluate(&white), evaluate(&black));
+    }
+
+    #[test]
+    fn side_to_move() {
+        let white = from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black = from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+        assert!(evaluate(&white) > 800);
+        assert_eq!(evaluate(&white), -evaluate(&black));
+        assert_eq!(breakdown(&white).material, 900);
+    }
+
+    #[test]
+    fn pawn_structure() {
+        // Doubled and isolated pawns for White, against a healthy pair.
+        let weak = breakdown(&from_fen("4k3/pp6/8/8/8/8/P7/P3K3 w - - 0 1"));
+        assert!(weak.pawn_structure < 0);
+
+        let passed = breakdown(&from_fen("4k3/8/1P6/8/8/8/p7/4K3 w - - 0 1"));
+        let blocked = breakdown(&from_fen("4k3/1p6/1P6/8/8/8/p7/4K3 w - - 0 1"));
+        assert!(passed.pawn_structure > blocked.pawn_structure);
+    }
+
+    #[test]
+    fn king_safety() {
+        let sheltered = breakdown(&from_fen("rnbq1rk1/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1RK1 w - - 0 1"));
+        let exposed = breakdown(&from_fen("rnbq1rk1/pppppppp/8/8/8/8/PPPPP3/RNBQ1RK1 w - - 0 1"));
+        assert_eq!(sheltered.king_safety, 0);
+        assert!(exposed.king_safety < 0);
+    }
+}
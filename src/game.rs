@@ -1,52 +1,262 @@
-use board::{Board, Tile};
+use board::{Board, Tile, Castling};
+use color::Color;
+use piece::{Piece, Rank};
 use ply::{Ply, Location, Move};
+use movegen::IllegalPly;
 use notation::{PlyInputNotation, BoardOutputNotation};
+use zobrist;
+
+/// A ply as it was played, along with what it captured and
+/// the state it replaced that can't be worked out from the ply.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LogEntry {
+    pub ply: Ply,
+    pub captured: Option<Piece>,
+    pub castling: Castling,
+    pub enpassant: Option<Location>,
+    pub halfmove_clock: u32,
+    /// The Zobrist hash of the board before the ply
+    pub hash: u64,
+}
 
 /// A game wraps a playable board.
+#[derive(Clone)]
 pub struct Game {
+    /// The state the game started from
+    pub start: Board,
     /// The current state
     pub board: Board,
     /// A vec of the plys
-    pub log: Vec<Ply>,
+    pub log: Vec<LogEntry>,
+    /// The Zobrist hash of the current board
+    hash: u64,
+    /// Plies that were undone, the most recent last
+    undone: Vec<Ply>,
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
-        Game{ board: Board::new(), log: Vec::new() }
+        Game::from_board(Board::new())
+    }
+
+    /// Start a game from any position, rather than the initial one.
+    pub fn from_board(board: Board) -> Game {
+        Game{
+            start: board,
+            board,
+            log: Vec::new(),
+            hash: board.zobrist(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// The Zobrist hash of the current board, kept up to date by play
+    /// and undo. It is only correct while the board is changed through
+    /// those alone.
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
-    pub fn parse_ply(&self, notation: &PlyInputNotation, input: &str) -> Option<Ply> {
+    pub fn parse_ply(&self, notation: &dyn PlyInputNotation, input: &str) -> Option<Ply> {
         notation.parse_ply(&self.board, input)
     }
 
-    pub fn unparse_board(&self, notation: &BoardOutputNotation) -> String {
+    pub fn unparse_board(&self, notation: &dyn BoardOutputNotation) -> String {
         notation.unparse_board(&self.board)
     }
 
     fn play_basic(&mut self, mv: Move, capture: Option<Location>) {
-        match capture {
-            Some(location) => {
-                self.board.grid[location.rank as usize][location.file as usize] = Tile::Empty;
-            },
-            None => (),
-        };
+        if let Some(location) = capture {
+            self.board.grid[location.rank as usize][location.file as usize] = Tile::Empty;
+        }
 
         // Move the piece to the new tile
-        // TODO: The move needs to be validated.
         self.board.grid[mv.to.rank as usize][mv.to.file as usize] = self.board.grid[mv.from.rank as usize][mv.from.file as usize];
         self.board.grid[mv.from.rank as usize][mv.from.file as usize] = Tile::Empty;
     }
 
-    pub fn play(&mut self, ply: &Ply) {
+    fn play_enpassant(&mut self, mv: Move, capture: Location) {
+        self.play_basic(mv, Some(capture));
+    }
+
+    fn play_promotion(&mut self, mv: Move, capture: Option<Location>, piece: Piece) {
+        self.play_basic(mv, capture);
+        self.board.grid[mv.to.rank as usize][mv.to.file as usize] = Tile::Taken(piece);
+    }
+
+    fn play_castling(&mut self, king: Move, rook: Move) {
+        self.play_basic(king, None);
+        self.play_basic(rook, None);
+    }
+
+    /// Bring the castling rights, en passant target and clocks up to date
+    /// for the ply, before its pieces are moved.
+    fn update_state(&mut self, ply: &Ply) {
+        let mv = ply.movement();
+        let moved = match *self.board.tile_at(&mv.from) {
+            Tile::Taken(piece) => piece,
+            Tile::Empty        => return,
+        };
+        let capture = match *ply {
+            Ply::Basic(_, capture) | Ply::Promotion(_, capture, _) => capture,
+            Ply::EnPassant(_, capture) => Some(capture),
+            Ply::Castling(..) => None,
+        };
+
+        // Anything leaving or arriving on a king or rook's home tile
+        // means that piece has moved or been captured.
+        self.revoke_castling(mv.from);
+        self.revoke_castling(mv.to);
+
+        self.board.enpassant = if moved.rank == Rank::Pawn && (mv.to.rank as i8 - mv.from.rank as i8).abs() == 2 {
+            Some(Location { rank: (mv.from.rank + mv.to.rank) / 2, file: mv.from.file })
+        } else {
+            None
+        };
+
+        if moved.rank == Rank::Pawn || capture.is_some() {
+            self.board.halfmove_clock = 0;
+        } else {
+            self.board.halfmove_clock += 1;
+        }
+
+        if self.board.color == Color::Black {
+            self.board.fullmove_number += 1;
+        }
+    }
+
+    fn revoke_castling(&mut self, location: Location) {
+        let castling = &mut self.board.castling;
+        match (location.rank, location.file) {
+            (0, 0) => castling.white_queen = false,
+            (0, 7) => castling.white_king = false,
+            (0, 4) => {
+                castling.white_king = false;
+                castling.white_queen = false;
+            },
+            (7, 0) => castling.black_queen = false,
+            (7, 7) => castling.black_king = false,
+            (7, 4) => {
+                castling.black_king = false;
+                castling.black_queen = false;
+            },
+            _ => (),
+        }
+    }
+
+    /// Play the ply for the side to move. If the ply is not legal,
+    /// or the game is over, the game is left untouched and the reason
+    /// is returned.
+    pub fn play(&mut self, ply: &Ply) -> Result<(), IllegalPly> {
+        if self.outcome().is_some() {
+            return Err(IllegalPly::GameOver);
+        }
+        self.board.validate(ply)?;
+        self.undone.clear();
+
+        let captured = match *ply {
+            Ply::Basic(_, capture) | Ply::Promotion(_, capture, _) => capture,
+            Ply::EnPassant(_, capture) => Some(capture),
+            Ply::Castling(..) => None,
+        }.and_then(|location| match *self.board.tile_at(&location) {
+            Tile::Taken(piece) => Some(piece),
+            Tile::Empty        => None,
+        });
+        self.log.push(LogEntry {
+            ply: *ply,
+            captured,
+            castling: self.board.castling,
+            enpassant: self.board.enpassant,
+            halfmove_clock: self.board.halfmove_clock,
+            hash: self.hash,
+        });
+
+        // Take out the keys for everything the ply changes, and put
+        // them back once it has been played.
+        let touched = zobrist::touched(ply);
+        let keys = |board: &Board| touched.iter()
+            .fold(zobrist::state_key(board), |key, &location| key ^ zobrist::tile_key(board, location));
+        self.hash ^= keys(&self.board);
+        self.update_state(ply);
+
         match *ply {
-            Ply::Basic(mv, capture) => self.play_basic(mv, capture),
-            _ => panic!("I don't know what to do with that ply yet."),
+            Ply::Basic(mv, capture)            => self.play_basic(mv, capture),
+            Ply::EnPassant(mv, capture)        => self.play_enpassant(mv, capture),
+            Ply::Promotion(mv, capture, piece) => self.play_promotion(mv, capture, piece),
+            Ply::Castling(king, rook)          => self.play_castling(king, rook),
         }
 
-        // If we didn't need to panic, then store the ply in the log.
-        self.log.push(*ply);
+        self.board.color = self.board.color.other();
+        self.hash ^= keys(&self.board);
+        Ok(())
+    }
+
+    fn unplay_basic(&mut self, mv: Move) {
+        self.board.grid[mv.from.rank as usize][mv.from.file as usize] = self.board.grid[mv.to.rank as usize][mv.to.file as usize];
+        self.board.grid[mv.to.rank as usize][mv.to.file as usize] = Tile::Empty;
+    }
+
+    /// Take back the last ply, restoring the board exactly as it was.
+    /// Returns the ply, or None if there was nothing to take back.
+    pub fn undo(&mut self) -> Option<Ply> {
+        let entry = self.log.pop()?;
+        self.hash = entry.hash;
 
         self.board.color = self.board.color.other();
+        let color = self.board.color;
+        if color == Color::Black {
+            self.board.fullmove_number -= 1;
+        }
+
+        let capture = match entry.ply {
+            Ply::Basic(mv, capture) => {
+                self.unplay_basic(mv);
+                capture
+            },
+            Ply::EnPassant(mv, capture) => {
+                self.unplay_basic(mv);
+                Some(capture)
+            },
+            Ply::Promotion(mv, capture, _) => {
+                self.unplay_basic(mv);
+                let pawn = Piece { rank: Rank::Pawn, color };
+                self.board.grid[mv.from.rank as usize][mv.from.file as usize] = Tile::Taken(pawn);
+                capture
+            },
+            Ply::Castling(king, rook) => {
+                self.unplay_basic(rook);
+                self.unplay_basic(king);
+                None
+            },
+        };
+        if let (Some(location), Some(piece)) = (capture, entry.captured) {
+            self.board.grid[location.rank as usize][location.file as usize] = Tile::Taken(piece);
+        }
+
+        self.board.castling = entry.castling;
+        self.board.enpassant = entry.enpassant;
+        self.board.halfmove_clock = entry.halfmove_clock;
+
+        self.undone.push(entry.ply);
+        Some(entry.ply)
+    }
+
+    /// Play again the last ply that was taken back. Returns the ply,
+    /// or None if nothing was taken back since the last ply was played.
+    pub fn redo(&mut self) -> Option<Ply> {
+        let ply = *self.undone.last()?;
+        let undone = std::mem::take(&mut self.undone);
+        let played = self.play(&ply);
+        self.undone = undone;
+        played.ok()?;
+        self.undone.pop();
+        Some(ply)
     }
 }
 
@@ -54,8 +264,12 @@ impl Game {
 #[cfg(test)]
 mod tests {
     use super::Game;
-    use board::Board;
-    use notation::PlyInputNotation;
+    use board::{Board, Tile};
+    use color::Color;
+    use movegen::IllegalPly;
+    use notation::{PlyInputNotation, ForsythEdwardsNotation, StandardAlgebraicNotation};
+    use notation::{BoardInputNotation, BoardOutputNotation};
+    use piece::{Piece, Rank};
     use ply::{Ply, Location, Move};
 
     #[test]
@@ -86,4 +300,231 @@ mod tests {
         let game = Game::new();
         assert!(game.parse_ply(notation, "random") == expected);
     }
+
+    #[test]
+    fn play() {
+        let mut game = Game::new();
+        let ply = Ply::Basic(Move {
+            from: Location { file: 4, rank: 1 },
+            to: Location { file: 4, rank: 3 },
+        }, None);
+        assert_eq!(game.play(&ply), Ok(()));
+        assert!(game.board.tile_at(&Location { file: 4, rank: 3 })
+                == &Tile::Taken(Piece { rank: Rank::Pawn, color: Color::White }));
+        assert!(game.board.color == Color::Black);
+        assert!(game.log.len() == 1);
+        assert!(game.log[0].ply == ply);
+        assert!(game.log[0].captured.is_none());
+    }
+
+    #[test]
+    fn play_illegal() {
+        let mut game = Game::new();
+        let plies = [
+            (Location { file: 4, rank: 3 }, Location { file: 4, rank: 4 }),
+            (Location { file: 4, rank: 6 }, Location { file: 4, rank: 4 }),
+            (Location { file: 0, rank: 0 }, Location { file: 0, rank: 2 }),
+        ];
+        let errors: Vec<_> = plies.iter().map(|&(from, to)| {
+            game.play(&Ply::Basic(Move { from, to }, None))
+        }).collect();
+        assert_eq!(errors, vec![
+            Err(IllegalPly::NoPiece(Location { file: 4, rank: 3 })),
+            Err(IllegalPly::WrongColor(Location { file: 4, rank: 6 })),
+            Err(IllegalPly::InvalidMovement),
+        ]);
+        assert!(game.board == Board::new());
+        assert!(game.log.is_empty());
+    }
+
+    #[test]
+    fn play_into_check() {
+        let mut game = Game::new();
+        game.board.grid[1][4] = Tile::Empty;
+        game.board.grid[6][4] = Tile::Empty;
+        game.board.grid[4][4] = Tile::Taken(Piece { rank: Rank::Rook, color: Color::Black });
+        game.board.grid[0][5] = Tile::Empty;
+        let ply = Ply::Basic(Move {
+            from: Location { file: 4, rank: 0 },
+            to: Location { file: 4, rank: 1 },
+        }, None);
+        assert_eq!(game.play(&ply), Err(IllegalPly::KingInCheck));
+    }
+
+    fn empty_game(color: Color) -> Game {
+        let mut board = Board::new();
+        board.grid = [[Tile::Empty; 8]; 8];
+        board.color = color;
+        board.grid[0][4] = Tile::Taken(Piece { rank: Rank::King, color: Color::White });
+        board.grid[7][4] = Tile::Taken(Piece { rank: Rank::King, color: Color::Black });
+        Game::from_board(board)
+    }
+
+    #[test]
+    fn play_enpassant() {
+        let mut game = empty_game(Color::White);
+        game.board.grid[4][4] = Tile::Taken(Piece { rank: Rank::Pawn, color: Color::White });
+        game.board.grid[4][3] = Tile::Taken(Piece { rank: Rank::Pawn, color: Color::Black });
+        game.board.enpassant = Some(Location { file: 3, rank: 5 });
+
+        let ply = Ply::EnPassant(Move {
+            from: Location { file: 4, rank: 4 },
+            to: Location { file: 3, rank: 5 },
+        }, Location { file: 3, rank: 4 });
+        assert_eq!(game.play(&ply), Ok(()));
+        assert!(game.board.grid[5][3] == Tile::Taken(Piece { rank: Rank::Pawn, color: Color::White }));
+        assert!(game.board.grid[4][3] == Tile::Empty);
+        assert!(game.board.grid[4][4] == Tile::Empty);
+    }
+
+    #[test]
+    fn play_promotion() {
+        let mut game = empty_game(Color::Black);
+        game.board.grid[1][1] = Tile::Taken(Piece { rank: Rank::Pawn, color: Color::Black });
+        game.board.grid[0][0] = Tile::Taken(Piece { rank: Rank::Rook, color: Color::White });
+
+        let knight = Piece { rank: Rank::Knight, color: Color::Black };
+        let ply = Ply::Promotion(Move {
+            from: Location { file: 1, rank: 1 },
+            to: Location { file: 0, rank: 0 },
+        }, Some(Location { file: 0, rank: 0 }), knight);
+        assert_eq!(game.play(&ply), Ok(()));
+        assert!(game.board.grid[0][0] == Tile::Taken(knight));
+        assert!(game.board.grid[1][1] == Tile::Empty);
+    }
+
+    #[test]
+    fn play_castling() {
+        let mut game = empty_game(Color::Black);
+        game.board.grid[7][0] = Tile::Taken(Piece { rank: Rank::Rook, color: Color::Black });
+
+        let ply = Ply::Castling(
+            Move { from: Location { file: 4, rank: 7 }, to: Location { file: 2, rank: 7 } },
+            Move { from: Location { file: 0, rank: 7 }, to: Location { file: 3, rank: 7 } },
+        );
+        assert_eq!(game.play(&ply), Ok(()));
+        assert!(game.board.grid[7][2] == Tile::Taken(Piece { rank: Rank::King, color: Color::Black }));
+        assert!(game.board.grid[7][3] == Tile::Taken(Piece { rank: Rank::Rook, color: Color::Black }));
+        assert!(game.board.grid[7][0] == Tile::Empty);
+        assert!(game.board.grid[7][4] == Tile::Empty);
+    }
+
+    #[test]
+    fn play_updates_state() {
+        let mut game = Game::new();
+        let fen = ForsythEdwardsNotation;
+        let plies = [
+            ((4, 1), (4, 3), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            ((2, 6), (2, 4), "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"),
+            ((6, 0), (5, 2), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"),
+        ];
+        for &((from_file, from_rank), (to_file, to_rank), expected) in plies.iter() {
+            let ply = Ply::Basic(Move {
+                from: Location { file: from_file, rank: from_rank },
+                to: Location { file: to_file, rank: to_rank },
+            }, None);
+            assert_eq!(game.play(&ply), Ok(()));
+            assert_eq!(game.unparse_board(&fen), expected);
+        }
+    }
+
+    #[test]
+    fn play_revokes_castling() {
+        let mut game = empty_game(Color::White);
+        game.board.grid[0][0] = Tile::Taken(Piece { rank: Rank::Rook, color: Color::White });
+        game.board.grid[7][7] = Tile::Taken(Piece { rank: Rank::Rook, color: Color::Black });
+
+        game.board.grid[7][0] = Tile::Taken(Piece { rank: Rank::Rook, color: Color::Black });
+
+        // The white rook captures the black rook on its home tile.
+        let ply = Ply::Basic(Move {
+            from: Location { file: 0, rank: 0 },
+            to: Location { file: 0, rank: 7 },
+        }, Some(Location { file: 0, rank: 7 }));
+        assert_eq!(game.play(&ply), Ok(()));
+        assert!(!game.board.castling.white_queen);
+        assert!(!game.board.castling.black_queen);
+        assert!(game.board.halfmove_clock == 0);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let fen = ForsythEdwardsNotation;
+        let start = fen.parse_board("r3k2r/1P6/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1").unwrap();
+        let mut game = Game::from_board(start);
+        let mut boards = vec![game.board];
+        for san in ["e4", "dxe3", "O-O", "Ke7", "bxa8=Q", "e2"].iter() {
+            let ply = game.parse_ply(&StandardAlgebraicNotation, san).unwrap();
+            assert_eq!(game.play(&ply), Ok(()));
+            boards.push(game.board);
+        }
+        assert_eq!(game.log[1].captured, Some(Piece { rank: Rank::Pawn, color: Color::White }));
+        assert_eq!(game.log[4].captured, Some(Piece { rank: Rank::Rook, color: Color::Black }));
+
+        for expected in boards.iter().rev().skip(1) {
+            assert!(game.undo().is_some());
+            assert_eq!(game.unparse_board(&fen), fen.unparse_board(expected));
+            assert_eq!(game.hash(), expected.zobrist());
+        }
+        assert!(game.undo().is_none());
+        assert!(game.log.is_empty());
+
+        for expected in boards.iter().skip(1) {
+            assert!(game.redo().is_some());
+            assert_eq!(game.unparse_board(&fen), fen.unparse_board(expected));
+            assert_eq!(game.hash(), expected.zobrist());
+        }
+        assert!(game.redo().is_none());
+    }
+
+    #[test]
+    fn play_clears_redo() {
+        let mut game = Game::new();
+        let ply = Ply::Basic(Move {
+            from: Location { file: 4, rank: 1 },
+            to: Location { file: 4, rank: 3 },
+        }, None);
+        game.play(&ply).unwrap();
+        assert_eq!(game.undo(), Some(ply));
+        assert!(game.board == Board::new());
+
+        let other = Ply::Basic(Move {
+            from: Location { file: 3, rank: 1 },
+            to: Location { file: 3, rank: 3 },
+        }, None);
+        game.play(&other).unwrap();
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn illegal_play_keeps_redo() {
+        let mut game = Game::new();
+        let ply = Ply::Basic(Move {
+            from: Location { file: 4, rank: 1 },
+            to: Location { file: 4, rank: 3 },
+        }, None);
+        game.play(&ply).unwrap();
+        game.undo();
+
+        let illegal = Ply::Basic(Move {
+            from: Location { file: 4, rank: 1 },
+            to: Location { file: 4, rank: 4 },
+        }, None);
+        assert!(game.play(&illegal).is_err());
+        assert_eq!(game.redo(), Some(ply));
+        assert_eq!(game.redo(), None);
+    }
+
+    #[test]
+    fn hash() {
+        let fen = ForsythEdwardsNotation;
+        let start = fen.parse_board("r3k2r/1P6/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1").unwrap();
+        let mut game = Game::from_board(start);
+        assert_eq!(game.hash(), start.zobrist());
+        for san in ["e4", "dxe3", "O-O", "Ke7", "bxa8=Q", "e2", "Qb7+", "Kd6", "Rfe1"].iter() {
+            let ply = game.parse_ply(&StandardAlgebraicNotation, san).unwrap();
+            game.play(&ply).unwrap();
+            assert_eq!(game.hash(), game.board.zobrist(), "{}", san);
+        }
+    }
 }
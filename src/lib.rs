@@ -3,12 +3,24 @@ extern crate regex;
 pub use color::Color;
 pub use piece::{Piece, Rank};
 pub use ply::{Location, Move, Ply};
-pub use board::{Board, Tile};
-pub use game::Game;
+pub use board::{Board, Tile, Castling};
+pub use game::{Game, LogEntry};
+pub use movegen::IllegalPly;
+pub use outcome::{Outcome, Reason};
+pub use bitboard::BitBoard;
+pub use perft::{perft, divide};
 
 mod color;
 mod piece;
 mod ply;
 mod board;
 mod game;
+mod movegen;
+mod attack;
+mod outcome;
+mod zobrist;
+mod perft;
+pub mod engine;
+pub mod eval;
+pub mod bitboard;
 pub mod notation;
@@ -0,0 +1,421 @@
+use std::error::Error;
+use std::fmt;
+
+use attack::{KNIGHT_OFFSETS, KING_OFFSETS, ROOK_DIRECTIONS, BISHOP_DIRECTIONS};
+use board::{Board, Tile};
+use color::Color;
+use piece::{Piece, Rank};
+use ply::{Location, Move, Ply};
+
+const PROMOTIONS: [Rank; 4] = [Rank::Queen, Rank::Rook, Rank::Bishop, Rank::Knight];
+
+/// The reason a ply may not be played on a board.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IllegalPly {
+    /// There is no piece on the origin tile.
+    NoPiece(Location),
+    /// The piece on the origin tile belongs to the other player.
+    WrongColor(Location),
+    /// The piece is not able to move that way.
+    InvalidMovement,
+    /// The ply would leave the mover's own king in check.
+    KingInCheck,
+    /// The king or rook has already given up the right to castle.
+    CastlingForfeited,
+    /// The king may not castle while it is in check.
+    CastlingOutOfCheck,
+    /// The king may not castle across an attacked tile.
+    CastlingThroughCheck,
+    /// The game has already come to an end.
+    GameOver,
+}
+
+impl fmt::Display for IllegalPly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IllegalPly::NoPiece(_)           => write!(f, "there is no piece to move there"),
+            IllegalPly::WrongColor(_)        => write!(f, "that piece belongs to the other player"),
+            IllegalPly::InvalidMovement      => write!(f, "that piece cannot move that way"),
+            IllegalPly::KingInCheck          => write!(f, "that would leave the king in check"),
+            IllegalPly::CastlingForfeited    => write!(f, "castling on that side is no longer allowed"),
+            IllegalPly::CastlingOutOfCheck   => write!(f, "the king cannot castle out of check"),
+            IllegalPly::CastlingThroughCheck => write!(f, "the king cannot castle through check"),
+            IllegalPly::GameOver             => write!(f, "the game is already over"),
+        }
+    }
+}
+
+impl Error for IllegalPly {}
+
+impl Board {
+    /// All of the plies that the side to move may legally play.
+    pub fn legal_plies(&self) -> Vec<Ply> {
+        self.pseudo_legal_plies()
+            .into_iter()
+            .filter(|ply| !self.exposes_king(ply))
+            .collect()
+    }
+
+    /// Check that the ply may be played by the side to move,
+    /// explaining why not if it may not.
+    pub fn validate(&self, ply: &Ply) -> Result<(), IllegalPly> {
+        let from = ply.movement().from;
+        match *self.tile_at(&from) {
+            Tile::Empty => return Err(IllegalPly::NoPiece(from)),
+            Tile::Taken(piece) if piece.color != self.color => {
+                return Err(IllegalPly::WrongColor(from));
+            },
+            Tile::Taken(_) => (),
+        }
+
+        if self.pseudo_legal_plies().contains(ply) {
+            return if self.exposes_king(ply) { Err(IllegalPly::KingInCheck) } else { Ok(()) };
+        }
+
+        if let Ply::Castling(king, rook) = *ply {
+            let allowed = match (self.color, rook.from.file) {
+                (Color::White, 7) => self.castling.white_king,
+                (Color::White, 0) => self.castling.white_queen,
+                (Color::Black, 7) => self.castling.black_king,
+                (Color::Black, 0) => self.castling.black_queen,
+                _ => return Err(IllegalPly::InvalidMovement),
+            };
+            if !allowed {
+                return Err(IllegalPly::CastlingForfeited);
+            }
+            if self.is_attacked(king.from, self.color.other()) {
+                return Err(IllegalPly::CastlingOutOfCheck);
+            }
+            if self.is_attacked(rook.to, self.color.other()) {
+                return Err(IllegalPly::CastlingThroughCheck);
+            }
+        }
+        Err(IllegalPly::InvalidMovement)
+    }
+
+    /// Plies that follow the movement rules of each piece,
+    /// without considering whether they leave the king in check.
+    fn pseudo_legal_plies(&self) -> Vec<Ply> {
+        let mut plies = Vec::new();
+        for rank in 0..8u8 {
+            for file in 0..8u8 {
+                let from = Location { rank, file };
+                if let Tile::Taken(piece) = *self.tile_at(&from) {
+                    if piece.color != self.color { continue; }
+                    match piece.rank {
+                        Rank::Pawn   => self.pawn_plies(from, &mut plies),
+                        Rank::Knight => self.step_plies(from, &KNIGHT_OFFSETS, &mut plies),
+                        Rank::Bishop => self.slide_plies(from, &BISHOP_DIRECTIONS, &mut plies),
+                        Rank::Rook   => self.slide_plies(from, &ROOK_DIRECTIONS, &mut plies),
+                        Rank::Queen  => {
+                            self.slide_plies(from, &ROOK_DIRECTIONS, &mut plies);
+                            self.slide_plies(from, &BISHOP_DIRECTIONS, &mut plies);
+                        },
+                        Rank::King   => {
+                            self.step_plies(from, &KING_OFFSETS, &mut plies);
+                            self.castling_plies(from, &mut plies);
+                        },
+                    }
+                }
+            }
+        }
+        plies
+    }
+
+    fn pawn_plies(&self, from: Location, plies: &mut Vec<Ply>) {
+        let (forward, start, last) = match self.color {
+            Color::White => (1, 1, 7),
+            Color::Black => (-1, 6, 0),
+        };
+
+        let mut targets = Vec::new();
+        if let Some(to) = from.offset(forward, 0) {
+            if *self.tile_at(&to) == Tile::Empty {
+                targets.push((to, None));
+                if from.rank == start {
+                    let double = to.offset(forward, 0).unwrap();
+                    if *self.tile_at(&double) == Tile::Empty {
+                        plies.push(Ply::Basic(Move { from, to: double }, None));
+                    }
+                }
+            }
+        }
+        for files in [-1, 1].iter() {
+            if let Some(to) = from.offset(forward, *files) {
+                match *self.tile_at(&to) {
+                    Tile::Taken(piece) if piece.color != self.color => {
+                        targets.push((to, Some(to)));
+                    },
+                    Tile::Empty if self.enpassant == Some(to) => {
+                        let captured = Location { rank: from.rank, file: to.file };
+                        plies.push(Ply::EnPassant(Move { from, to }, captured));
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        for (to, capture) in targets {
+            let mv = Move { from, to };
+            if to.rank == last {
+                for rank in PROMOTIONS.iter() {
+                    let piece = Piece { rank: *rank, color: self.color };
+                    plies.push(Ply::Promotion(mv, capture, piece));
+                }
+            } else {
+                plies.push(Ply::Basic(mv, capture));
+            }
+        }
+    }
+
+    fn step_plies(&self, from: Location, offsets: &[(i8, i8)], plies: &mut Vec<Ply>) {
+        for &(ranks, files) in offsets {
+            if let Some(to) = from.offset(ranks, files) {
+                match *self.tile_at(&to) {
+                    Tile::Empty => plies.push(Ply::Basic(Move { from, to }, None)),
+                    Tile::Taken(piece) if piece.color != self.color => {
+                        plies.push(Ply::Basic(Move { from, to }, Some(to)));
+                    },
+                    Tile::Taken(_) => (),
+                }
+            }
+        }
+    }
+
+    fn slide_plies(&self, from: Location, directions: &[(i8, i8)], plies: &mut Vec<Ply>) {
+        for &(ranks, files) in directions {
+            let mut next = from.offset(ranks, files);
+            while let Some(to) = next {
+                match *self.tile_at(&to) {
+                    Tile::Empty => plies.push(Ply::Basic(Move { from, to }, None)),
+                    Tile::Taken(piece) => {
+                        if piece.color != self.color {
+                            plies.push(Ply::Basic(Move { from, to }, Some(to)));
+                        }
+                        break;
+                    },
+                }
+                next = to.offset(ranks, files);
+            }
+        }
+    }
+
+    fn castling_plies(&self, from: Location, plies: &mut Vec<Ply>) {
+        let (home, king_side, queen_side) = match self.color {
+            Color::White => (0, self.castling.white_king, self.castling.white_queen),
+            Color::Black => (7, self.castling.black_king, self.castling.black_queen),
+        };
+        if from != (Location { rank: home, file: 4 }) { return; }
+        if self.is_attacked(from, self.color.other()) { return; }
+
+        // (rook file, king destination, rook destination, files that must be empty)
+        let sides: [(bool, u8, u8, u8, &[u8]); 2] = [
+            (king_side, 7, 6, 5, &[5, 6]),
+            (queen_side, 0, 2, 3, &[1, 2, 3]),
+        ];
+        let rook = Tile::Taken(Piece { rank: Rank::Rook, color: self.color });
+        for &(allowed, rook_file, king_to, rook_to, between) in sides.iter() {
+            if !allowed { continue; }
+            if *self.tile_at(&Location { rank: home, file: rook_file }) != rook { continue; }
+            if between.iter().any(|&file| {
+                *self.tile_at(&Location { rank: home, file }) != Tile::Empty
+            }) { continue; }
+            // The king may not pass through an attacked square.
+            let passing = Location { rank: home, file: rook_to };
+            if self.is_attacked(passing, self.color.other()) { continue; }
+
+            plies.push(Ply::Castling(
+                Move { from, to: Location { rank: home, file: king_to } },
+                Move {
+                    from: Location { rank: home, file: rook_file },
+                    to: Location { rank: home, file: rook_to },
+                },
+            ));
+        }
+    }
+
+    /// Whether playing the ply would leave the mover's own king in check.
+    fn exposes_king(&self, ply: &Ply) -> bool {
+        let mut after = *self;
+        after.shift_pieces(ply);
+        match after.find_king(self.color) {
+            Some(king) => after.is_attacked(king, self.color.other()),
+            None       => false,
+        }
+    }
+
+    /// Move the pieces on the grid as the ply describes,
+    /// leaving the rest of the board state alone.
+    fn shift_pieces(&mut self, ply: &Ply) {
+        let (mv, capture, rook) = match *ply {
+            Ply::Basic(mv, capture)        => (mv, capture, None),
+            Ply::EnPassant(mv, capture)    => (mv, Some(capture), None),
+            Ply::Promotion(mv, capture, _) => (mv, capture, None),
+            Ply::Castling(king, rook)      => (king, None, Some(rook)),
+        };
+        if let Some(location) = capture {
+            self.grid[location.rank as usize][location.file as usize] = Tile::Empty;
+        }
+        for mv in Some(mv).iter().chain(rook.iter()) {
+            self.grid[mv.to.rank as usize][mv.to.file as usize] = *self.tile_at(&mv.from);
+            self.grid[mv.from.rank as usize][mv.from.file as usize] = Tile::Empty;
+        }
+        if let Ply::Promotion(mv, _, piece) = *ply {
+            self.grid[mv.to.rank as usize][mv.to.file as usize] = Tile::Taken(piece);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::{Board, Tile, Castling};
+    use color::Color;
+    use piece::{Piece, Rank};
+    use ply::{Location, Move, Ply};
+    use super::IllegalPly;
+
+    fn empty_board(color: Color) -> Board {
+        Board {
+            grid: [[Tile::Empty; 8]; 8],
+            color,
+            castling: Castling {
+                white_king: false,
+                white_queen: false,
+                black_king: false,
+                black_queen: false,
+            },
+            enpassant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    fn place(board: &mut Board, rank: u8, file: u8, color: Color, piece: Rank) {
+        board.grid[rank as usize][file as usize] = Tile::Taken(Piece { rank: piece, color });
+    }
+
+    fn basic(from: (u8, u8), to: (u8, u8), capture: bool) -> Ply {
+        let to = Location { rank: to.0, file: to.1 };
+        Ply::Basic(Move { from: Location { rank: from.0, file: from.1 }, to },
+                   if capture { Some(to) } else { None })
+    }
+
+    #[test]
+    fn initial_position() {
+        let plies = Board::new().legal_plies();
+        assert_eq!(plies.len(), 20);
+        assert!(plies.contains(&basic((1, 4), (3, 4), false)));
+        assert!(plies.contains(&basic((0, 6), (2, 5), false)));
+    }
+
+    #[test]
+    fn pinned_piece_cannot_move() {
+        let mut board = empty_board(Color::White);
+        place(&mut board, 0, 4, Color::White, Rank::King);
+        place(&mut board, 1, 4, Color::White, Rank::Knight);
+        place(&mut board, 7, 4, Color::Black, Rank::Rook);
+        place(&mut board, 7, 0, Color::Black, Rank::King);
+        let plies = board.legal_plies();
+        assert!(plies.iter().all(|ply| match *ply {
+            Ply::Basic(mv, _) => mv.from == Location { rank: 0, file: 4 },
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn must_escape_check() {
+        let mut board = empty_board(Color::White);
+        place(&mut board, 0, 0, Color::White, Rank::King);
+        place(&mut board, 3, 3, Color::White, Rank::Rook);
+        place(&mut board, 7, 0, Color::Black, Rank::Rook);
+        place(&mut board, 7, 7, Color::Black, Rank::King);
+        let plies = board.legal_plies();
+        assert!(plies.contains(&basic((3, 3), (3, 0), false)));
+        assert!(plies.contains(&basic((0, 0), (0, 1), false)));
+        assert!(!plies.contains(&basic((3, 3), (3, 4), false)));
+        assert!(!plies.contains(&basic((0, 0), (1, 0), false)));
+    }
+
+    #[test]
+    fn en_passant() {
+        let mut board = empty_board(Color::White);
+        place(&mut board, 0, 4, Color::White, Rank::King);
+        place(&mut board, 7, 4, Color::Black, Rank::King);
+        place(&mut board, 4, 4, Color::White, Rank::Pawn);
+        place(&mut board, 4, 3, Color::Black, Rank::Pawn);
+        board.enpassant = Some(Location { rank: 5, file: 3 });
+        let expected = Ply::EnPassant(Move {
+            from: Location { rank: 4, file: 4 },
+            to: Location { rank: 5, file: 3 },
+        }, Location { rank: 4, file: 3 });
+        assert!(board.legal_plies().contains(&expected));
+    }
+
+    #[test]
+    fn promotion() {
+        let mut board = empty_board(Color::Black);
+        place(&mut board, 0, 4, Color::White, Rank::King);
+        place(&mut board, 7, 4, Color::Black, Rank::King);
+        place(&mut board, 1, 0, Color::Black, Rank::Pawn);
+        let promotions = board.legal_plies().into_iter().filter(|ply| {
+            matches!(*ply, Ply::Promotion(..))
+        }).count();
+        assert_eq!(promotions, 4);
+    }
+
+    #[test]
+    fn castling() {
+        let mut board = empty_board(Color::White);
+        board.castling.white_king = true;
+        board.castling.white_queen = true;
+        place(&mut board, 0, 4, Color::White, Rank::King);
+        place(&mut board, 0, 0, Color::White, Rank::Rook);
+        place(&mut board, 0, 7, Color::White, Rank::Rook);
+        place(&mut board, 7, 4, Color::Black, Rank::King);
+        // Covers d1, so queenside castling would pass through check.
+        place(&mut board, 7, 3, Color::Black, Rank::Rook);
+
+        let castles: Vec<Ply> = board.legal_plies().into_iter().filter(|ply| {
+            matches!(*ply, Ply::Castling(..))
+        }).collect();
+        assert_eq!(castles, vec![Ply::Castling(
+            Move { from: Location { rank: 0, file: 4 }, to: Location { rank: 0, file: 6 } },
+            Move { from: Location { rank: 0, file: 7 }, to: Location { rank: 0, file: 5 } },
+        )]);
+    }
+
+    #[test]
+    fn validate() {
+        let board = Board::new();
+        assert_eq!(board.validate(&basic((1, 4), (3, 4), false)), Ok(()));
+        assert_eq!(board.validate(&basic((3, 4), (4, 4), false)),
+                   Err(IllegalPly::NoPiece(Location { rank: 3, file: 4 })));
+        assert_eq!(board.validate(&basic((6, 4), (4, 4), false)),
+                   Err(IllegalPly::WrongColor(Location { rank: 6, file: 4 })));
+        assert_eq!(board.validate(&basic((1, 4), (4, 4), false)),
+                   Err(IllegalPly::InvalidMovement));
+    }
+
+    #[test]
+    fn validate_castling() {
+        let mut board = empty_board(Color::White);
+        board.castling.white_queen = true;
+        place(&mut board, 0, 4, Color::White, Rank::King);
+        place(&mut board, 0, 0, Color::White, Rank::Rook);
+        place(&mut board, 0, 7, Color::White, Rank::Rook);
+        place(&mut board, 7, 4, Color::Black, Rank::King);
+        place(&mut board, 7, 3, Color::Black, Rank::Rook);
+
+        let king_side = Ply::Castling(
+            Move { from: Location { rank: 0, file: 4 }, to: Location { rank: 0, file: 6 } },
+            Move { from: Location { rank: 0, file: 7 }, to: Location { rank: 0, file: 5 } },
+        );
+        let queen_side = Ply::Castling(
+            Move { from: Location { rank: 0, file: 4 }, to: Location { rank: 0, file: 2 } },
+            Move { from: Location { rank: 0, file: 0 }, to: Location { rank: 0, file: 3 } },
+        );
+        assert_eq!(board.validate(&king_side), Err(IllegalPly::CastlingForfeited));
+        assert_eq!(board.validate(&queen_side), Err(IllegalPly::CastlingThroughCheck));
+    }
+}
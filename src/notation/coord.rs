@@ -0,0 +1,165 @@
+use ply::{Ply, Move};
+use board::{Board, Tile};
+use piece::Rank;
+use regex;
+
+use notation::{PlyInputNotation, PlyOutputNotation, parse_location};
+
+
+/// Coordinate Notation
+/// Takes the algebraic names of the positions of the move.
+/// Captures, castling and en passant are determined automatically.
+/// Both positions must be written full-form. A promotion may name
+/// the piece to promote to, otherwise it promotes to a queen.
+///
+/// Examples:
+///
+/// a1 a2
+/// h8 h7
+/// e1 g1
+/// b7 b8 n
+pub struct CoordinateNotation;
+
+impl PlyInputNotation for CoordinateNotation {
+    fn parse_ply(&self, board: &Board, input: &str) -> Option<Ply> {
+        let re = regex!(r"^([a-h])([1-8]) *([a-h])([1-8]) *([qrbnQRBN])?");
+        let captures = re.captures(input);
+        match captures {
+            None           => None,
+            Some(captures) => {
+                let from = parse_location(captures.at(1), captures.at(2));
+                let to = parse_location(captures.at(3), captures.at(4));
+
+                match (from, to) {
+                    (Some(from), Some(to)) => {
+                        let promotion = self.parse_promotion(captures.at(5));
+                        let legal = board.legal_plies().into_iter().find(|ply| {
+                            ply.movement() == Move { from, to } && match *ply {
+                                Ply::Promotion(_, _, piece) => piece.rank == promotion,
+                                _ => true,
+                            }
+                        });
+                        if legal.is_some() {
+                            return legal;
+                        }
+
+                        let capture = match *board.tile_at(&to) {
+                            Tile::Empty    => None,
+                            Tile::Taken(_) => Some(to),
+                        };
+                        Some(Ply::Basic(Move { from, to }, capture))
+                    },
+                    _ => None,
+                }
+            },
+        }
+    }
+}
+
+impl PlyOutputNotation for CoordinateNotation {
+    fn unparse_ply(&self, _: &Board, ply: &Ply) -> String {
+        let mv = ply.movement();
+        let mut output = String::new();
+        for location in [mv.from, mv.to].iter() {
+            if !output.is_empty() {
+                output.push(' ');
+            }
+            output.push((b'a' + location.file) as char);
+            output.push((b'1' + location.rank) as char);
+        }
+        if let Ply::Promotion(_, _, piece) = *ply {
+            output.push_str(match piece.rank {
+                Rank::Rook   => " r",
+                Rank::Bishop => " b",
+                Rank::Knight => " n",
+                _            => " q",
+            });
+        }
+        output
+    }
+}
+
+impl CoordinateNotation {
+    fn parse_promotion(&self, rank: Option<&str>) -> Rank {
+        match rank.unwrap_or("") {
+            "r" | "R" => Rank::Rook,
+            "b" | "B" => Rank::Bishop,
+            "n" | "N" => Rank::Knight,
+            _         => Rank::Queen,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::{Board, Tile};
+    use color::Color;
+    use piece::{Piece, Rank};
+    use ply::{Ply, Location, Move};
+    use super::CoordinateNotation;
+    use notation::{PlyInputNotation, PlyOutputNotation};
+
+    #[test]
+    fn parse_ply_nocapture() {
+        let notation = CoordinateNotation;
+        let ply = notation.parse_ply(&Board::new(), "a2 a3");
+
+        let expected = Some(Ply::Basic(Move {
+            from: Location { file: 0, rank: 1 },
+            to: Location { file: 0, rank: 2 },
+        }, None));
+        assert_eq!(ply, expected);
+    }
+
+    #[test]
+    fn parse_ply_capture() {
+        let notation = CoordinateNotation;
+        let ply = notation.parse_ply(&Board::new(), "h8 h7");
+
+        let expected = Some(Ply::Basic(Move {
+            from: Location { file: 7, rank: 7 },
+            to: Location { file: 7, rank: 6 },
+        }, Some(Location { file: 7, rank: 6 })));
+        assert_eq!(ply, expected);
+    }
+
+    #[test]
+    fn parse_ply_castling() {
+        let mut board = Board::new();
+        board.grid[0][5] = Tile::Empty;
+        board.grid[0][6] = Tile::Empty;
+        let ply = CoordinateNotation.parse_ply(&board, "e1 g1");
+
+        let expected = Some(Ply::Castling(
+            Move { from: Location { file: 4, rank: 0 }, to: Location { file: 6, rank: 0 } },
+            Move { from: Location { file: 7, rank: 0 }, to: Location { file: 5, rank: 0 } },
+        ));
+        assert_eq!(ply, expected);
+    }
+
+    #[test]
+    fn parse_ply_promotion() {
+        let mut board = Board::new();
+        board.grid[6][1] = Tile::Taken(Piece { rank: Rank::Pawn, color: Color::White });
+        let ply = CoordinateNotation.parse_ply(&board, "b7 a8 n");
+
+        let expected = Some(Ply::Promotion(Move {
+            from: Location { file: 1, rank: 6 },
+            to: Location { file: 0, rank: 7 },
+        }, Some(Location { file: 0, rank: 7 }), Piece { rank: Rank::Knight, color: Color::White }));
+        assert_eq!(ply, expected);
+    }
+
+    #[test]
+    fn unparse_ply() {
+        let notation = CoordinateNotation;
+        let mut board = Board::new();
+        let ply = notation.parse_ply(&board, "g1 f3").unwrap();
+        assert_eq!(notation.unparse_ply(&board, &ply), "g1 f3");
+
+        board.grid[6][1] = Tile::Taken(Piece { rank: Rank::Pawn, color: Color::White });
+        let ply = notation.parse_ply(&board, "b7 a8 n").unwrap();
+        assert_eq!(notation.unparse_ply(&board, &ply), "b7 a8 n");
+    }
+}
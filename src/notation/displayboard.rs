@@ -6,36 +6,49 @@ use notation::{BoardOutputNotation, BoardOutputNotationHelper};
 
 pub struct DisplayBoardNotation<T: BoardOutputNotationHelper> {
     helper: T,
+    /// Whether Black's side is drawn at the bottom
+    flipped: bool,
+    /// Whether the grid is drawn with box-drawing characters, not ASCII
+    box_drawing: bool,
 }
 
 impl<T: BoardOutputNotationHelper> BoardOutputNotation for DisplayBoardNotation<T> {
     fn unparse_board(&self, board: &Board) -> String {
         let mut output = String::new();
+        let files: Vec<u8> = if self.flipped { (0..8).rev().collect() } else { (0..8).collect() };
+        let ranks: Vec<u8> = if self.flipped { (0..8).collect() } else { (0..8).rev().collect() };
 
         // The header
-        output.push_str(" ");
-        for file in 0..8u8 {
+        output.push(' ');
+        for &file in files.iter() {
             output.push_str("   ");
             output.push_str(&self.helper.file_label(file));
         }
-        output.push_str("\n");
+        output.push('\n');
 
         // The grid
-        let divider = "  +---+---+---+---+---+---+---+---+\n";
-        output.push_str(divider);
-
-        for rank in (0..8u8).rev() {
+        let (top, middle, bottom, wall) = if self.box_drawing {
+            (self.divider('┌', '┬', '┐'), self.divider('├', '┼', '┤'), self.divider('└', '┴', '┘'), '│')
+        } else {
+            let divider = self.divider('+', '+', '+');
+            (divider.clone(), divider.clone(), divider, '|')
+        };
+        output.push_str(&top);
+
+        for (index, &rank) in ranks.iter().enumerate() {
             output.push_str(&self.helper.rank_label(rank));
-            output.push_str(" |");
-            for file in 0..8u8 {
-                output.push_str(" ");
+            output.push(' ');
+            output.push(wall);
+            for &file in files.iter() {
+                output.push(' ');
                 output.push_str(&self.helper.unparse_tile(board.tile_at(
-                    &Location { file: file, rank: rank }
+                    &Location { file, rank }
                 )));
-                output.push_str(" |");
+                output.push(' ');
+                output.push(wall);
             }
-            output.push_str("\n");
-            output.push_str(divider);
+            output.push('\n');
+            output.push_str(if index == 7 { &bottom } else { &middle });
         }
 
         // The footer, with supporting information
@@ -54,7 +67,34 @@ impl<T: BoardOutputNotationHelper> BoardOutputNotation for DisplayBoardNotation<
 
 impl<T: BoardOutputNotationHelper> DisplayBoardNotation<T> {
     pub fn new(helper: T) -> DisplayBoardNotation<T> {
-        DisplayBoardNotation::<T> { helper: helper }
+        DisplayBoardNotation::<T> { helper, flipped: false, box_drawing: false }
+    }
+
+    /// Draw the grid with Unicode box-drawing characters, rather than
+    /// with ASCII.
+    pub fn set_box_drawing(&mut self, box_drawing: bool) {
+        self.box_drawing = box_drawing;
+    }
+
+    // A horizontal line across the grid, with the given corners and joints.
+    fn divider(&self, left: char, joint: char, right: char) -> String {
+        let line = if self.box_drawing { "───" } else { "---" };
+        let mut output = "  ".to_string();
+        output.push(left);
+        for file in 0..8 {
+            if file > 0 {
+                output.push(joint);
+            }
+            output.push_str(line);
+        }
+        output.push(right);
+        output.push('\n');
+        output
+    }
+
+    /// Turn the board around, to draw it from the other player's side.
+    pub fn flip(&mut self) {
+        self.flipped = !self.flipped;
     }
 
     fn unparse_castling(&self, castling: &Castling) -> String {
@@ -65,7 +105,7 @@ impl<T: BoardOutputNotationHelper> DisplayBoardNotation<T> {
         output.push_str(if castling.black_king { "k" } else { "" });
         output.push_str(if castling.black_queen { "q" } else { "" });
 
-        if &output == "" { output.push_str("-") };
+        if output.is_empty() { output.push('-') };
         output
     }
 
@@ -119,7 +159,7 @@ mod tests {
 
     #[test]
     fn unparse_board_new() {
-        let notation = DisplayBoardNotation { helper: TestHelper };
+        let notation = DisplayBoardNotation::new(TestHelper);
         let expected = "    0   1   2   3   4   5   6   7\n  \
                           +---+---+---+---+---+---+---+---+\n\
                         7 | r | n | b | q | k | b | n | r |\n  \
@@ -142,4 +182,31 @@ mod tests {
         let board = Board::new();
         assert_eq!(notation.unparse_board(&board), expected);
     }
+
+    #[test]
+    fn unparse_board_flipped() {
+        let mut notation = DisplayBoardNotation::new(TestHelper);
+        notation.flip();
+        let output = notation.unparse_board(&Board::new());
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "    7   6   5   4   3   2   1   0");
+        assert_eq!(lines[2], "0 | R | N | B | K | Q | B | N | R |");
+        assert_eq!(lines[16], "7 | r | n | b | k | q | b | n | r |");
+
+        notation.flip();
+        assert!(notation.unparse_board(&Board::new()).starts_with("    0   1"));
+    }
+
+    #[test]
+    fn unparse_board_box_drawing() {
+        let mut notation = DisplayBoardNotation::new(TestHelper);
+        notation.set_box_drawing(true);
+        let output = notation.unparse_board(&Board::new());
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "  ┌───┬───┬───┬───┬───┬───┬───┬───┐");
+        assert_eq!(lines[2], "7 │ r │ n │ b │ q │ k │ b │ n │ r │");
+        assert_eq!(lines[3], "  ├───┼───┼───┼───┼───┼───┼───┼───┤");
+        assert_eq!(lines[17], "  └───┴───┴───┴───┴───┴───┴───┴───┘");
+        assert_eq!(lines.len(), 19);
+    }
 }
@@ -1,15 +1,89 @@
+use std::error::Error;
+use std::fmt;
+
 use ply::Location;
 use board::{Board, Tile, Castling};
 use piece::{Piece, Rank};
 use color::Color;
 
-use notation::BoardOutputNotation;
+use notation::{BoardInputNotation, BoardOutputNotation};
 
 
 /// Forsyth-Edwards Notation (FEN).
 /// Describes the current state of a board.
 pub struct ForsythEdwardsNotation;
 
+/// The reason a FEN record could not be parsed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FenError {
+    /// The record needs six space-separated fields (or four, without clocks).
+    FieldCount(usize),
+    /// The piece placement needs eight ranks separated by slashes.
+    RankCount(usize),
+    /// A rank, counted from 1 at the bottom, doesn't describe eight tiles.
+    RankLength(u8),
+    /// A character in the piece placement isn't a piece or a count of empty tiles.
+    UnknownPiece(char),
+    /// The active color must be "w" or "b".
+    ActiveColor(String),
+    /// The castling field must be "-" or some of "KQkq".
+    Castling(String),
+    /// The en passant target must be "-" or a tile the last ply skipped over.
+    EnPassant(String),
+    /// The halfmove clock must be a non-negative number.
+    HalfmoveClock(String),
+    /// The fullmove number must be a positive number.
+    FullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FenError::FieldCount(count)       => write!(f, "expected 4 or 6 fields, found {}", count),
+            FenError::RankCount(count)        => write!(f, "expected 8 ranks, found {}", count),
+            FenError::RankLength(rank)        => write!(f, "rank {} does not have 8 tiles", rank),
+            FenError::UnknownPiece(chr)       => write!(f, "unknown piece '{}'", chr),
+            FenError::ActiveColor(ref field)  => write!(f, "invalid active color '{}'", field),
+            FenError::Castling(ref field)     => write!(f, "invalid castling availability '{}'", field),
+            FenError::EnPassant(ref field)    => write!(f, "invalid en passant target '{}'", field),
+            FenError::HalfmoveClock(ref field) => write!(f, "invalid halfmove clock '{}'", field),
+            FenError::FullmoveNumber(ref field) => write!(f, "invalid fullmove number '{}'", field),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl BoardInputNotation for ForsythEdwardsNotation {
+    type Error = FenError;
+
+    fn parse_board(&self, input: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = input.split_whitespace().collect();
+        if fields.len() != 6 && fields.len() != 4 {
+            return Err(FenError::FieldCount(fields.len()));
+        }
+
+        let color = self.parse_color(fields[1])?;
+        Ok(Board {
+            grid: self.parse_grid(fields[0])?,
+            color,
+            castling: self.parse_castling(fields[2])?,
+            enpassant: self.parse_enpassant(fields[3], color)?,
+            halfmove_clock: match fields.get(4) {
+                Some(field) => field.parse().map_err(|_| FenError::HalfmoveClock(field.to_string()))?,
+                None        => 0,
+            },
+            fullmove_number: match fields.get(5) {
+                Some(field) => match field.parse() {
+                    Ok(number) if number > 0 => number,
+                    _ => return Err(FenError::FullmoveNumber(field.to_string())),
+                },
+                None        => 1,
+            },
+        })
+    }
+}
+
 impl BoardOutputNotation for ForsythEdwardsNotation {
     fn unparse_board(&self, board: &Board) -> String {
         format!(
@@ -25,6 +99,111 @@ impl BoardOutputNotation for ForsythEdwardsNotation {
 }
 
 impl ForsythEdwardsNotation {
+    fn parse_grid(&self, field: &str) -> Result<[[Tile; 8]; 8], FenError> {
+        let ranks: Vec<&str> = field.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+
+        let mut grid = [[Tile::Empty; 8]; 8];
+        // Rows are in reverse order: from the top to the bottom.
+        for (index, rank) in ranks.iter().enumerate() {
+            grid[7 - index] = self.parse_rank(rank, 8 - index as u8)?;
+        }
+        Ok(grid)
+    }
+
+    fn parse_rank(&self, field: &str, label: u8) -> Result<[Tile; 8], FenError> {
+        let mut row = [Tile::Empty; 8];
+        let mut file = 0;
+        for chr in field.chars() {
+            let (tile, count) = match chr.to_digit(10) {
+                Some(count @ 1..=8) => (Tile::Empty, count as usize),
+                _ => (Tile::Taken(self.parse_piece(chr)?), 1),
+            };
+            for _ in 0..count {
+                match row.get_mut(file) {
+                    Some(slot) => *slot = tile,
+                    None       => return Err(FenError::RankLength(label)),
+                }
+                file += 1;
+            }
+        }
+        if file != 8 {
+            return Err(FenError::RankLength(label));
+        }
+        Ok(row)
+    }
+
+    fn parse_piece(&self, chr: char) -> Result<Piece, FenError> {
+        let rank = match chr.to_ascii_lowercase() {
+            'p' => Rank::Pawn,
+            'r' => Rank::Rook,
+            'n' => Rank::Knight,
+            'b' => Rank::Bishop,
+            'q' => Rank::Queen,
+            'k' => Rank::King,
+            _   => return Err(FenError::UnknownPiece(chr)),
+        };
+        let color = if chr.is_uppercase() { Color::White } else { Color::Black };
+        Ok(Piece { rank, color })
+    }
+
+    fn parse_color(&self, field: &str) -> Result<Color, FenError> {
+        match field {
+            "w" => Ok(Color::White),
+            "b" => Ok(Color::Black),
+            _   => Err(FenError::ActiveColor(field.to_string())),
+        }
+    }
+
+    fn parse_castling(&self, field: &str) -> Result<Castling, FenError> {
+        let mut castling = Castling {
+            white_king: false,
+            white_queen: false,
+            black_king: false,
+            black_queen: false,
+        };
+        if field == "-" {
+            return Ok(castling);
+        }
+
+        if field.is_empty() {
+            return Err(FenError::Castling(field.to_string()));
+        }
+        for chr in field.chars() {
+            let right = match chr {
+                'K' => &mut castling.white_king,
+                'Q' => &mut castling.white_queen,
+                'k' => &mut castling.black_king,
+                'q' => &mut castling.black_queen,
+                _   => return Err(FenError::Castling(field.to_string())),
+            };
+            if *right {
+                return Err(FenError::Castling(field.to_string()));
+            }
+            *right = true;
+        }
+        Ok(castling)
+    }
+
+    fn parse_enpassant(&self, field: &str, color: Color) -> Result<Option<Location>, FenError> {
+        if field == "-" {
+            return Ok(None);
+        }
+
+        // The target is the tile behind a pawn of the side that just moved.
+        let expected_rank = match color { Color::White => '6', Color::Black => '3' };
+        let chars: Vec<char> = field.chars().collect();
+        match chars[..] {
+            [file @ 'a'..='h', rank] if rank == expected_rank => Ok(Some(Location {
+                file: file as u8 - b'a',
+                rank: rank as u8 - b'1',
+            })),
+            _ => Err(FenError::EnPassant(field.to_string())),
+        }
+    }
+
     fn unparse_grid(&self, grid: &[[Tile; 8]; 8]) -> String {
         // Rows are in reverse order: from the top to the bottom.
         format!(
@@ -95,7 +274,7 @@ impl ForsythEdwardsNotation {
         if castling.black_king { output.push('k'); };
         if castling.black_queen { output.push('q'); };
 
-        if &output == "" { output.push('-'); };
+        if output.is_empty() { output.push('-'); };
 
         output
     }
@@ -105,18 +284,18 @@ impl ForsythEdwardsNotation {
             None           => "-".to_string(),
             Some(location) => {
                 let file = match location.file {
-                    0 => "A",
-                    1 => "B",
-                    2 => "C",
-                    3 => "D",
-                    4 => "E",
-                    5 => "F",
-                    6 => "G",
-                    7 => "H",
+                    0 => "a",
+                    1 => "b",
+                    2 => "c",
+                    3 => "d",
+                    4 => "e",
+                    5 => "f",
+                    6 => "g",
+                    7 => "h",
                     _ => panic!("Internal En passant file invalid."),
                 };
                 let rank = if location.rank < 8 {
-                    location.rank.to_string()
+                    (location.rank + 1).to_string()
                 } else {
                     panic!("Internal En passant rank invalid.")
                 };
@@ -129,9 +308,12 @@ impl ForsythEdwardsNotation {
 
 #[cfg(test)]
 mod tests {
-    use board::Board;
-    use notation::BoardOutputNotation;
-    use super::ForsythEdwardsNotation;
+    use board::{Board, Tile};
+    use color::Color;
+    use piece::{Piece, Rank};
+    use ply::Location;
+    use notation::{BoardInputNotation, BoardOutputNotation};
+    use super::{ForsythEdwardsNotation, FenError};
 
     #[test]
     fn unparse_board_initial() {
@@ -140,4 +322,65 @@ mod tests {
         let fen = notation.unparse_board(&board);
         assert_eq!(&fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
     }
+
+    #[test]
+    fn unparse_board_enpassant() {
+        let mut board = Board::new();
+        board.enpassant = Some(Location { file: 4, rank: 2 });
+        let fen = ForsythEdwardsNotation.unparse_board(&board);
+        assert_eq!(&fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1")
+    }
+
+    #[test]
+    fn parse_board_initial() {
+        let notation = ForsythEdwardsNotation;
+        let board = notation.parse_board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(board, Ok(Board::new()));
+    }
+
+    #[test]
+    fn parse_board_roundtrip() {
+        let notation = ForsythEdwardsNotation;
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b Kq e3 7 42";
+        let board = notation.parse_board(fen).unwrap();
+        assert!(board.grid[7][0] == Tile::Taken(Piece { rank: Rank::Rook, color: Color::Black }));
+        assert!(board.grid[4][3] == Tile::Taken(Piece { rank: Rank::Pawn, color: Color::White }));
+        assert!(board.color == Color::Black);
+        assert!(board.castling.white_king && !board.castling.white_queen);
+        assert!(!board.castling.black_king && board.castling.black_queen);
+        assert!(board.enpassant == Some(Location { file: 4, rank: 2 }));
+        assert!(board.halfmove_clock == 7);
+        assert!(board.fullmove_number == 42);
+        assert_eq!(notation.unparse_board(&board), fen);
+    }
+
+    #[test]
+    fn parse_board_without_clocks() {
+        let notation = ForsythEdwardsNotation;
+        let board = notation.parse_board("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+        assert_eq!(board, Ok(Board::new()));
+    }
+
+    #[test]
+    fn parse_board_errors() {
+        let notation = ForsythEdwardsNotation;
+        let cases = [
+            ("8/8/8/8/8/8/8/8 w - - 0", FenError::FieldCount(5)),
+            ("8/8/8/8/8/8/8 w - - 0 1", FenError::RankCount(7)),
+            ("8/8/8/8/8/8/8/ppppppppp w - - 0 1", FenError::RankLength(1)),
+            ("7/8/8/8/8/8/8/8 w - - 0 1", FenError::RankLength(8)),
+            ("8/8/8/8/8/8/8/7x w - - 0 1", FenError::UnknownPiece('x')),
+            ("8/8/8/8/8/8/8/8 white - - 0 1", FenError::ActiveColor("white".to_string())),
+            ("8/8/8/8/8/8/8/8 w KK - 0 1", FenError::Castling("KK".to_string())),
+            ("8/8/8/8/8/8/8/8 w Kx - 0 1", FenError::Castling("Kx".to_string())),
+            ("8/8/8/8/8/8/8/8 w - e3 0 1", FenError::EnPassant("e3".to_string())),
+            ("8/8/8/8/8/8/8/8 w - i6 0 1", FenError::EnPassant("i6".to_string())),
+            ("8/8/8/8/8/8/8/8 w - - x 1", FenError::HalfmoveClock("x".to_string())),
+            ("8/8/8/8/8/8/8/8 w - - 0 0", FenError::FullmoveNumber("0".to_string())),
+        ];
+        for &(fen, ref error) in cases.iter() {
+            assert_eq!(notation.parse_board(fen).as_ref(), Err(error));
+        }
+        assert_eq!(FenError::FieldCount(5).to_string(), "expected 4 or 6 fields, found 5");
+    }
 }
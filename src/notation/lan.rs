@@ -0,0 +1,128 @@
+use ply::Ply;
+use board::Board;
+use piece::Rank;
+use regex;
+
+use notation::{PlyInputNotation, PlyOutputNotation, parse_location};
+
+
+/// Long Algebraic Notation, as spoken by the Universal Chess Interface (UCI).
+/// The origin and destination are written together without separators,
+/// followed by the lowercase piece for a promotion. Castling is written
+/// as the move of the king.
+///
+/// Examples:
+///
+/// e2e4
+/// e7e8q
+/// e1g1
+pub struct LongAlgebraicNotation;
+
+impl PlyInputNotation for LongAlgebraicNotation {
+    fn parse_ply(&self, board: &Board, input: &str) -> Option<Ply> {
+        let re = regex!(r"^([a-h])([1-8])([a-h])([1-8])([qrbn])?$");
+        let captures = re.captures(input.trim())?;
+        let from = parse_location(captures.at(1), captures.at(2))?;
+        let to = parse_location(captures.at(3), captures.at(4))?;
+        let promotion = match captures.at(5) {
+            Some("q") => Some(Rank::Queen),
+            Some("r") => Some(Rank::Rook),
+            Some("b") => Some(Rank::Bishop),
+            Some("n") => Some(Rank::Knight),
+            _         => None,
+        };
+
+        board.legal_plies().into_iter().find(|ply| {
+            let mv = ply.movement();
+            let promotes = match *ply {
+                Ply::Promotion(_, _, piece) => Some(piece.rank),
+                _ => None,
+            };
+            mv.from == from && mv.to == to && promotes == promotion
+        })
+    }
+}
+
+impl PlyOutputNotation for LongAlgebraicNotation {
+    fn unparse_ply(&self, _: &Board, ply: &Ply) -> String {
+        let mv = ply.movement();
+        let mut output = String::new();
+        for location in [mv.from, mv.to].iter() {
+            output.push((b'a' + location.file) as char);
+            output.push((b'1' + location.rank) as char);
+        }
+        if let Ply::Promotion(_, _, piece) = *ply {
+            output.push(match piece.rank {
+                Rank::Rook   => 'r',
+                Rank::Bishop => 'b',
+                Rank::Knight => 'n',
+                _            => 'q',
+            });
+        }
+        output
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use color::Color;
+    use piece::{Piece, Rank};
+    use ply::{Ply, Location, Move};
+    use notation::{BoardInputNotation, ForsythEdwardsNotation, PlyInputNotation, PlyOutputNotation};
+    use super::LongAlgebraicNotation;
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    #[test]
+    fn parse_ply_basic() {
+        let notation = LongAlgebraicNotation;
+        assert_eq!(notation.parse_ply(&Board::new(), "e2e4"), Some(Ply::Basic(Move {
+            from: Location { file: 4, rank: 1 },
+            to: Location { file: 4, rank: 3 },
+        }, None)));
+        assert_eq!(notation.parse_ply(&Board::new(), "e2e5"), None);
+        assert_eq!(notation.parse_ply(&Board::new(), "e2 e4"), None);
+    }
+
+    #[test]
+    fn parse_ply_special() {
+        let notation = LongAlgebraicNotation;
+        let board = from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert_eq!(notation.parse_ply(&board, "e1g1"), Some(Ply::Castling(
+            Move { from: Location { file: 4, rank: 0 }, to: Location { file: 6, rank: 0 } },
+            Move { from: Location { file: 7, rank: 0 }, to: Location { file: 5, rank: 0 } },
+        )));
+
+        let board = from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        assert_eq!(notation.parse_ply(&board, "e5d6"), Some(Ply::EnPassant(Move {
+            from: Location { file: 4, rank: 4 },
+            to: Location { file: 3, rank: 5 },
+        }, Location { file: 3, rank: 4 })));
+
+        let board = from_fen("4k3/8/8/8/8/8/p7/4K3 b - - 0 1");
+        let mv = Move { from: Location { file: 0, rank: 1 }, to: Location { file: 0, rank: 0 } };
+        assert_eq!(notation.parse_ply(&board, "a2a1q"),
+                   Some(Ply::Promotion(mv, None, Piece { rank: Rank::Queen, color: Color::Black })));
+        assert_eq!(notation.parse_ply(&board, "a2a1"), None);
+    }
+
+    #[test]
+    fn unparse_ply() {
+        let notation = LongAlgebraicNotation;
+        let cases = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"),
+            ("4k3/8/8/8/8/8/p7/4K3 b - - 0 1", "a2a1n"),
+        ];
+        for &(fen, lan) in cases.iter() {
+            let board = from_fen(fen);
+            let ply = notation.parse_ply(&board, lan).unwrap();
+            assert_eq!(notation.unparse_ply(&board, &ply), lan);
+        }
+    }
+}
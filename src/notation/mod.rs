@@ -1,9 +1,12 @@
 use ply::{Ply, Location};
 use board::{Board, Tile};
 
-pub use notation::fen::ForsythEdwardsNotation;
+pub use notation::fen::{ForsythEdwardsNotation, FenError};
 pub use notation::san::StandardAlgebraicNotation;
+pub use notation::coord::CoordinateNotation;
+pub use notation::lan::LongAlgebraicNotation;
 pub use notation::displayboard::DisplayBoardNotation;
+pub use notation::unicode::UnicodeNotation;
 
 
 // Shortcut macro for creating a regular expression.
@@ -13,11 +16,45 @@ macro_rules! regex {
 }
 
 
+// Parse the file and rank labels of a location, such as "e" and "4".
+fn parse_location(file: Option<&str>, rank: Option<&str>) -> Option<Location> {
+    let file = match file.unwrap_or("") {
+        "a" => Some(0),
+        "b" => Some(1),
+        "c" => Some(2),
+        "d" => Some(3),
+        "e" => Some(4),
+        "f" => Some(5),
+        "g" => Some(6),
+        "h" => Some(7),
+        _   => None,
+    };
+    let rank: Option<u8> = rank.unwrap_or("").parse::<u8>().ok();
+    match (file, rank) {
+        (Some(file), Some(rank)) if (0 < rank && rank < 9) => {
+            Some(Location { file, rank: rank - 1u8})
+        },
+        _ => None,
+    }
+}
+
+
 pub trait PlyInputNotation {
     fn parse_ply(&self, board: &Board, input: &str) -> Option<Ply>;
 }
 
 
+pub trait PlyOutputNotation {
+    fn unparse_ply(&self, board: &Board, ply: &Ply) -> String;
+}
+
+
+pub trait BoardInputNotation {
+    type Error;
+    fn parse_board(&self, input: &str) -> Result<Board, Self::Error>;
+}
+
+
 pub trait BoardOutputNotation {
     fn unparse_board(&self, board: &Board) -> String;
 }
@@ -32,4 +69,8 @@ pub trait BoardOutputNotationHelper {
 
 mod fen;
 mod san;
+mod coord;
+mod lan;
+pub mod pgn;
 mod displayboard;
+mod unicode;
@@ -0,0 +1,580 @@
+use std::error::Error;
+use std::fmt;
+
+use board::Board;
+use color::Color;
+use game::Game;
+use notation::{BoardInputNotation, ForsythEdwardsNotation, FenError};
+use notation::{PlyInputNotation, PlyOutputNotation, BoardOutputNotation, StandardAlgebraicNotation};
+
+// The Seven Tag Roster, in the order it must be written.
+const ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+const LINE_WIDTH: usize = 80;
+
+
+/// A game read from Portable Game Notation (PGN).
+pub struct PgnGame {
+    /// The tag pairs, in the order they were written.
+    pub tags: Vec<(String, String)>,
+    /// The game with its mainline played through.
+    pub game: Game,
+    /// The result token that ended the movetext, "*" if it was missing.
+    pub result: String,
+}
+
+impl PgnGame {
+    /// Wrap a game to be written out, with no tags. The result is the
+    /// game's outcome, or "*" while it is still being played.
+    pub fn new(game: Game) -> PgnGame {
+        let result = game.outcome().map_or("*", |outcome| outcome.score()).to_string();
+        PgnGame { tags: Vec::new(), game, result }
+    }
+
+    /// The value of the first tag pair with the given name.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter()
+            .find(|&(tag, _)| tag == name)
+            .map(|(_, value)| &value[..])
+    }
+
+    /// Set the value of a tag pair, replacing any it already has.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|tag| tag.0 == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None      => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Write the game in PGN export format: the Seven Tag Roster first,
+    /// then any other tags, then the movetext in SAN wrapped to 80 columns.
+    /// Games that don't start from the initial position get SetUp and FEN tags.
+    pub fn unparse_pgn(&self) -> String {
+        let mut tags: Vec<(&str, String)> = ROSTER.iter().map(|&name| {
+            let value = match name {
+                "Result" => self.result.clone(),
+                "Date"   => self.tag(name).unwrap_or("????.??.??").to_string(),
+                _        => self.tag(name).unwrap_or("?").to_string(),
+            };
+            (name, value)
+        }).collect();
+        for (name, value) in self.tags.iter() {
+            if !ROSTER.contains(&&name[..]) && name != "SetUp" && name != "FEN" {
+                tags.push((name, value.clone()));
+            }
+        }
+        if self.game.start != Board::new() {
+            tags.push(("SetUp", "1".to_string()));
+            tags.push(("FEN", ForsythEdwardsNotation.unparse_board(&self.game.start)));
+        }
+
+        let mut output = String::new();
+        for (name, value) in tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            output.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        output.push('\n');
+
+        let mut line = String::new();
+        for token in self.movetext() {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                output.push_str(&line);
+                output.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        output.push_str(&line);
+        output.push('\n');
+        output
+    }
+
+    // The move numbers, plies and result, replayed from the start.
+    fn movetext(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut game = Game::from_board(self.game.start);
+        for ply in self.game.log.iter().map(|entry| &entry.ply) {
+            let board = game.board;
+            match board.color {
+                Color::White => tokens.push(format!("{}.", board.fullmove_number)),
+                Color::Black if tokens.is_empty() => tokens.push(format!("{}...", board.fullmove_number)),
+                Color::Black => (),
+            }
+            tokens.push(StandardAlgebraicNotation.unparse_ply(&board, ply));
+            if game.play(ply).is_err() {
+                break;
+            }
+        }
+        tokens.push(self.result.clone());
+        tokens
+    }
+}
+
+/// The reason a game could not be read from PGN.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PgnError {
+    /// A tag pair was not of the form [Name "Value"].
+    Tag(String),
+    /// The FEN tag did not describe a board.
+    Fen(FenError),
+    /// A move in the movetext could not be understood or played.
+    Ply { number: u32, color: Color, token: String },
+    /// A comment or variation was still open at the end of the input.
+    Unterminated(char),
+    /// A closing bracket or brace had nothing to close.
+    Unexpected(char),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PgnError::Tag(ref tag)     => write!(f, "invalid tag pair [{}]", tag),
+            PgnError::Fen(ref error)   => write!(f, "invalid FEN tag: {}", error),
+            PgnError::Ply { number, color, ref token } => {
+                let dots = match color { Color::White => ".", Color::Black => "..." };
+                write!(f, "cannot play move {}{} {}", number, dots, token)
+            },
+            PgnError::Unterminated(chr) => write!(f, "unterminated '{}'", chr),
+            PgnError::Unexpected(chr)   => write!(f, "unexpected '{}'", chr),
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+/// Reads each game in turn from Portable Game Notation (PGN).
+/// Comments, annotation glyphs and variations are skipped,
+/// and the mainline is replayed through a Game.
+///
+/// http://www.saremba.de/chessgml/standards/pgn/pgn-complete.htm
+pub struct PgnReader<'a> {
+    input: &'a str,
+}
+
+enum Token<'a> {
+    Tag(&'a str),
+    Symbol(&'a str),
+}
+
+impl<'a> PgnReader<'a> {
+    pub fn new(input: &'a str) -> PgnReader<'a> {
+        PgnReader { input }
+    }
+
+    fn read_game(&mut self) -> Result<PgnGame, PgnError> {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(tag)) = self.peek()? {
+            self.advance(tag.len() + 2);
+            tags.push(self.parse_tag(tag)?);
+        }
+
+        let board = match tags.iter().find(|&(name, _)| name == "FEN") {
+            Some((_, fen)) => ForsythEdwardsNotation.parse_board(fen).map_err(PgnError::Fen)?,
+            None           => Board::new(),
+        };
+        let mut game = Game::from_board(board);
+        let mut result = "*".to_string();
+
+        while let Some(Token::Symbol(symbol)) = self.peek()? {
+            self.advance(symbol.len());
+            let token = self.strip_move_number(symbol);
+            match token {
+                "1-0" | "0-1" | "1/2-1/2" | "*" => {
+                    result = token.to_string();
+                    break;
+                },
+                _ if token.chars().all(|chr| chr == '!' || chr == '?') => continue,
+                _ => (),
+            }
+
+            let played = match StandardAlgebraicNotation.parse_ply(&game.board, token) {
+                Some(ply) => game.play(&ply).is_ok(),
+                None      => false,
+            };
+            if !played {
+                return Err(PgnError::Ply {
+                    number: game.board.fullmove_number,
+                    color: game.board.color,
+                    token: token.to_string(),
+                });
+            }
+        }
+
+        Ok(PgnGame { tags, game, result })
+    }
+
+    // Skip what is left of a game that could not be read.
+    fn skip_game(&mut self) {
+        loop {
+            match self.peek() {
+                Ok(Some(Token::Symbol(symbol))) => {
+                    self.advance(symbol.len());
+                    match self.strip_move_number(symbol) {
+                        "1-0" | "0-1" | "1/2-1/2" | "*" => break,
+                        _ => (),
+                    }
+                },
+                Err(PgnError::Unexpected(_)) => (),
+                // Nothing more can be read once a comment runs to the end.
+                Err(PgnError::Unterminated(_)) => {
+                    self.input = "";
+                    break;
+                },
+                _ => break,
+            }
+        }
+    }
+
+    fn advance(&mut self, length: usize) {
+        self.input = &self.input[length..];
+    }
+
+    // Skip whitespace, comments, annotation glyphs and variations,
+    // then look at the tag or symbol that follows.
+    fn peek(&mut self) -> Result<Option<Token<'a>>, PgnError> {
+        loop {
+            let trimmed = self.input.trim_start();
+            let at_line_start = self.input[..self.input.len() - trimmed.len()].contains('\n');
+            self.input = trimmed;
+
+            let chr = match self.input.chars().next() {
+                Some(chr) => chr,
+                None      => return Ok(None),
+            };
+            match chr {
+                '{' => self.skip_past('{', '}')?,
+                ';' => self.skip_line(),
+                '%' if at_line_start => self.skip_line(),
+                '$' => {
+                    let length = self.input[1..].find(|chr: char| !chr.is_ascii_digit())
+                        .map_or(self.input.len(), |length| length + 1);
+                    self.advance(length);
+                },
+                '(' => self.skip_variation()?,
+                ']' | ')' | '}' => {
+                    self.advance(1);
+                    return Err(PgnError::Unexpected(chr));
+                },
+                '[' => {
+                    let mut quoted = false;
+                    let mut escaped = false;
+                    for (index, chr) in self.input.char_indices().skip(1) {
+                        match chr {
+                            _ if escaped       => escaped = false,
+                            '\\' if quoted     => escaped = true,
+                            '"'                => quoted = !quoted,
+                            ']' if !quoted     => return Ok(Some(Token::Tag(&self.input[1..index]))),
+                            _                  => (),
+                        }
+                    }
+                    return Err(PgnError::Unterminated('['));
+                },
+                _ => {
+                    let length = self.input.find(|chr: char| {
+                        chr.is_whitespace() || "[]{}();$".contains(chr)
+                    }).unwrap_or(self.input.len());
+                    return Ok(Some(Token::Symbol(&self.input[..length])));
+                },
+            }
+        }
+    }
+
+    fn skip_past(&mut self, open: char, close: char) -> Result<(), PgnError> {
+        match self.input.find(close) {
+            Some(index) => {
+                self.advance(index + 1);
+                Ok(())
+            },
+            None => Err(PgnError::Unterminated(open)),
+        }
+    }
+
+    fn skip_line(&mut self) {
+        let length = self.input.find('\n').unwrap_or(self.input.len());
+        self.advance(length);
+    }
+
+    fn skip_variation(&mut self) -> Result<(), PgnError> {
+        let mut depth = 0;
+        while let Some(chr) = self.input.chars().next() {
+            match chr {
+                '{' => { self.skip_past('{', '}')?; continue; },
+                ';' => { self.skip_line(); continue; },
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _   => (),
+            }
+            self.advance(chr.len_utf8());
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+        Err(PgnError::Unterminated('('))
+    }
+
+    fn parse_tag(&self, tag: &str) -> Result<(String, String), PgnError> {
+        let invalid = || PgnError::Tag(tag.to_string());
+        let tag = tag.trim();
+        let split = tag.find(|chr: char| !(chr.is_alphanumeric() || chr == '_')).ok_or_else(invalid)?;
+        let (name, value) = (&tag[..split], tag[split..].trim());
+        if name.is_empty() || value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+            return Err(invalid());
+        }
+
+        let mut unescaped = String::new();
+        let mut escaped = false;
+        for chr in value[1..value.len() - 1].chars() {
+            if chr == '\\' && !escaped {
+                escaped = true;
+            } else if chr == '"' && !escaped {
+                return Err(invalid());
+            } else {
+                unescaped.push(chr);
+                escaped = false;
+            }
+        }
+        Ok((name.to_string(), unescaped))
+    }
+
+    // Move numbers may be written on their own, or run into the move.
+    fn strip_move_number(&self, symbol: &'a str) -> &'a str {
+        let digits = symbol.find(|chr: char| !chr.is_ascii_digit()).unwrap_or(symbol.len());
+        if digits > 0 && symbol[digits..].starts_with('.') {
+            symbol[digits..].trim_start_matches('.')
+        } else {
+            symbol
+        }
+    }
+}
+
+impl<'a> Iterator for PgnReader<'a> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Result<PgnGame, PgnError>> {
+        match self.peek() {
+            Ok(None) => return None,
+            Err(error) => {
+                // Nothing more can be read once a comment runs to the end.
+                if let PgnError::Unterminated(_) = error {
+                    self.input = "";
+                }
+                return Some(Err(error));
+            },
+            Ok(Some(_)) => (),
+        }
+
+        let game = self.read_game();
+        if game.is_err() {
+            self.skip_game();
+        }
+        Some(game)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use color::Color;
+    use notation::{BoardOutputNotation, ForsythEdwardsNotation};
+    use game::Game;
+    use notation::{BoardInputNotation, StandardAlgebraicNotation};
+    use super::{PgnGame, PgnReader, PgnError};
+
+    const GAMES: &str = r#"
+[Event "Casual \"Blitz\""]
+[Site "?"]
+[Date "????.??.??"]
+[Round "1"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 {King's pawn; the most popular} e5 2. Qh5 $2 (2. Nf3 Nc6 {transposes}
+(2... d6)) 2... Nc6 3.Bc4 Nf6?? ; a blunder
+4. Qxf7# 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 30"]
+
+30. e4 Kd7 31. e5 *
+"#;
+
+    #[test]
+    fn read_games() {
+        let mut reader = PgnReader::new(GAMES);
+
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first.tags.len(), 7);
+        assert_eq!(first.tag("Event"), Some("Casual \"Blitz\""));
+        assert_eq!(first.tag("White"), Some("A"));
+        assert_eq!(first.result, "1-0");
+        assert_eq!(first.game.log.len(), 7);
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&first.game.board),
+                   "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4");
+
+        let second = reader.next().unwrap().unwrap();
+        assert_eq!(second.tag("Event"), Some("Second"));
+        assert_eq!(second.result, "*");
+        assert_eq!(ForsythEdwardsNotation.unparse_board(&second.game.board),
+                   "8/3k4/8/4P3/8/8/8/4K3 b - - 0 31");
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn read_error_then_continue() {
+        let input = "[Event \"Bad\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n\n[Event \"Good\"]\n\n1. d4 *\n";
+        let mut reader = PgnReader::new(input);
+        match reader.next() {
+            Some(Err(error)) => assert_eq!(error, PgnError::Ply {
+                number: 2,
+                color: Color::White,
+                token: "Ke3".to_string(),
+            }),
+            _ => panic!("Expected an error."),
+        }
+        let good = reader.next().unwrap().unwrap();
+        assert_eq!(good.tag("Event"), Some("Good"));
+        assert_eq!(good.game.log.len(), 1);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn read_errors() {
+        let cases = [
+            ("[Event Unquoted]\n*", PgnError::Tag("Event Unquoted".to_string())),
+            ("[Event \"a\"\n[Site \"b\"]\n*", PgnError::Tag("Event \"a\"\n[Site \"b\"".to_string())),
+            ("1. e4 {never closed", PgnError::Unterminated('{')),
+            ("1. e4 (1. d4 d5", PgnError::Unterminated('(')),
+            ("1. e4 ] e5 *", PgnError::Unexpected(']')),
+            ("1. e4 ) e5 *", PgnError::Unexpected(')')),
+            ("1. e4 } e5 *", PgnError::Unexpected('}')),
+        ];
+        for &(input, ref error) in cases.iter() {
+            assert_eq!(PgnReader::new(input).next().map(|game| game.err()), Some(Some(error.clone())));
+        }
+    }
+
+    #[test]
+    fn read_unterminated_once() {
+        for &input in ["1. e4 { x", "1. e4 (1. d4", "[Event \"x\"]\n\n1. e4 {"].iter() {
+            let games: Vec<_> = PgnReader::new(input).map(|game| game.err()).collect();
+            assert_eq!(games.len(), 1, "{}", input);
+            assert!(games[0].is_some(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn read_stray_delimiter_then_continue() {
+        let mut reader = PgnReader::new("1. e4 ] e5 *\n\n] 1. d4 *\n");
+        assert_eq!(reader.next().map(|game| game.err()), Some(Some(PgnError::Unexpected(']'))));
+        assert_eq!(reader.next().map(|game| game.err()), Some(Some(PgnError::Unexpected(']'))));
+        assert_eq!(reader.next().unwrap().unwrap().game.log.len(), 1);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn read_past_claimable_draw() {
+        let input = "1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8 5. e4 e5 6. Qh5 Ke7 7. Qxe5# 1-0";
+        let pgn = PgnReader::new(input).next().unwrap().unwrap();
+        assert_eq!(pgn.game.log.len(), 13);
+        assert_eq!(pgn.result, "1-0");
+        assert_eq!(pgn.movetext()[pgn.movetext().len() - 2], "Qxe5#");
+    }
+
+    fn play(game: &mut Game, plies: &[&str]) {
+        for san in plies {
+            let ply = game.parse_ply(&StandardAlgebraicNotation, san).unwrap();
+            game.play(&ply).unwrap();
+        }
+    }
+
+    #[test]
+    fn unparse_pgn() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"]);
+        let mut pgn = PgnGame::new(game);
+        pgn.set_tag("Annotator", "Somebody");
+        pgn.set_tag("White", "Scholar \"Sr.\"");
+        pgn.set_tag("White", "Scholar");
+        pgn.result = "1-0".to_string();
+        assert_eq!(pgn.unparse_pgn(), "[Event \"?\"]\n\
+                                        [Site \"?\"]\n\
+                                        [Date \"????.??.??\"]\n\
+                                        [Round \"?\"]\n\
+                                        [White \"Scholar\"]\n\
+                                        [Black \"?\"]\n\
+                                        [Result \"1-0\"]\n\
+                                        [Annotator \"Somebody\"]\n\
+                                        \n\
+                                        1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n");
+    }
+
+    #[test]
+    fn new_takes_result_from_outcome() {
+        let mut game = Game::new();
+        play(&mut game, &["f3", "e5"]);
+        assert_eq!(PgnGame::new(game.clone()).result, "*");
+        play(&mut game, &["g4", "Qh4#"]);
+        let output = PgnGame::new(game).unparse_pgn();
+        assert!(output.contains("[Result \"0-1\"]\n"));
+        assert!(output.ends_with(" Qh4# 0-1\n"));
+
+        let stalemate = ForsythEdwardsNotation.parse_board("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(PgnGame::new(Game::from_board(stalemate)).result, "1/2-1/2");
+    }
+
+    #[test]
+    fn unparse_pgn_setup() {
+        let board = ForsythEdwardsNotation.parse_board("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30").unwrap();
+        let mut game = Game::from_board(board);
+        play(&mut game, &["Kd7", "e4"]);
+        let mut pgn = PgnGame::new(game);
+        pgn.set_tag("Event", "Endgame \\ \"study\"");
+        let output = pgn.unparse_pgn();
+        assert!(output.starts_with("[Event \"Endgame \\\\ \\\"study\\\"\"]\n"));
+        assert!(output.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n\n"));
+        assert!(output.ends_with("\n30... Kd7 31. e4 *\n"));
+
+        let read = PgnReader::new(&output).next().unwrap().unwrap();
+        assert_eq!(read.tag("Event"), Some("Endgame \\ \"study\""));
+        assert!(read.game.board == pgn.game.board);
+    }
+
+    #[test]
+    fn unparse_pgn_wraps() {
+        let mut game = Game::new();
+        // Push a pair of pawns now and then, so that no position comes
+        // up five times and ends the game.
+        for &pawns in [&["a3", "a6"][..], &["h3", "h6"], &[]].iter() {
+            for _ in 0..3 {
+                play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+            }
+            play(&mut game, pawns);
+        }
+        let output = PgnGame::new(game).unparse_pgn();
+        let movetext: Vec<&str> = output.lines().skip(8).collect();
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|line| line.len() <= 80));
+        assert_eq!(movetext.join(" ").split_whitespace().count(), 40 + 20 + 1);
+    }
+
+    #[test]
+    fn unparse_pgn_wraps_finished_game() {
+        let mut game = Game::new();
+        play(&mut game, &[
+            "e4", "e5", "Nf3", "d6", "d4", "Bg4", "dxe5", "Bxf3", "Qxf3", "dxe5", "Bc4", "Nf6",
+            "Qb3", "Qe7", "Nc3", "c6", "Bg5", "b5", "Nxb5", "cxb5", "Bxb5+", "Nbd7", "O-O-O", "Rd8",
+            "Rxd7", "Rxd7", "Rd1", "Qe6", "Bxd7+", "Nxd7", "Qb8+", "Nxb8", "Rd8#",
+        ]);
+        let output = PgnGame::new(game).unparse_pgn();
+        let movetext: Vec<&str> = output.lines().skip(8).collect();
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|line| line.len() <= 80));
+        assert_eq!(movetext.join(" ").split_whitespace().count(), 33 + 17 + 1);
+        assert!(movetext.join(" ").ends_with("17. Rd8# 1-0"));
+    }
+}
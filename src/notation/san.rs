@@ -1,21 +1,26 @@
-use ply::{Ply, Location, Move};
+use ply::{Ply, Location};
 use board::{Board, Tile};
 use color::Color;
 use piece::{Piece, Rank};
 use regex;
 
-use notation::{PlyInputNotation, BoardOutputNotationHelper};
+use bitboard::BitBoard;
+use notation::{PlyInputNotation, PlyOutputNotation, BoardOutputNotationHelper, parse_location};
 
 
-/// Standard Algebraic Notation
-/// Takes the standard algebraic notation of the positions of the move.
-/// Captures are determined automatically.
-/// Both positions must be written full-form.
+/// Standard Algebraic Notation (SAN)
+/// Names the piece that moves and the tile it moves to, with just enough
+/// of its origin to tell it apart from any other piece that could move there.
+/// The ply is resolved against the legal plies of the board.
 ///
 /// Examples:
 ///
-/// a1 a2
-/// h8 h7
+/// e4
+/// Nf3
+/// exd5
+/// O-O-O
+/// e8=Q+
+/// Rad1
 pub struct StandardAlgebraicNotation;
 
 impl BoardOutputNotationHelper for StandardAlgebraicNotation {
@@ -54,48 +59,145 @@ impl BoardOutputNotationHelper for StandardAlgebraicNotation {
 
 impl PlyInputNotation for StandardAlgebraicNotation {
     fn parse_ply(&self, board: &Board, input: &str) -> Option<Ply> {
-        let re = regex!(r"^([a-h])([1-8]) *([a-h])([1-8])");
-        let captures = re.captures(input);
-        match captures {
-            None           => None,
-            Some(captures) => {
-                let from = self.parse_location(captures.at(1), captures.at(2));
-                let to = self.parse_location(captures.at(3), captures.at(4));
-
-                match (from, to) {
-                    (Some(from), Some(to)) => {
-                        let capture = match *board.tile_at(&to) {
-                            Tile::Empty    => None,
-                            Tile::Taken(_) => Some(to),
-                        };
-                        Some(Ply::Basic(Move { from: from, to: to }, capture))
-                    },
-                    _ => None,
-                }
-            },
+        let re = regex!(concat!(
+            r"^(?:(O-O-O|0-0-0)|(O-O|0-0)|",
+            r"([NBRQK])?([a-h])?([1-8])?(x)?([a-h][1-8])(?:=?([NBRQ]))?)",
+            r"[+#]?[!?]*$"
+        ));
+        let captures = re.captures(input.trim())?;
+
+        if captures.at(1).is_some() || captures.at(2).is_some() {
+            let file = if captures.at(1).is_some() { 2 } else { 6 };
+            return self.only(board.legal_plies().into_iter().filter(|ply| match *ply {
+                Ply::Castling(king, _) => king.to.file == file,
+                _ => false,
+            }));
+        }
+
+        let rank = self.parse_rank(captures.at(3)).unwrap_or(Rank::Pawn);
+        let from_file = captures.at(4).map(|file| file.as_bytes()[0] - b'a');
+        let from_rank = captures.at(5).map(|rank| rank.as_bytes()[0] - b'1');
+        let capture = captures.at(6).is_some();
+        let to = captures.at(7).and_then(|to| parse_location(Some(&to[..1]), Some(&to[1..])));
+        let promotion = self.parse_rank(captures.at(8));
+        // A pawn capture always names the file the pawn came from.
+        if rank == Rank::Pawn && capture && from_file.is_none() {
+            return None;
+        }
+
+        self.only(board.legal_plies().into_iter().filter(|ply| {
+            let mv = ply.movement();
+            let moved = match *board.tile_at(&mv.from) {
+                Tile::Taken(piece) => piece.rank,
+                Tile::Empty        => return false,
+            };
+            let (captures, promotes) = match *ply {
+                Ply::Basic(_, capture)            => (capture.is_some(), None),
+                Ply::EnPassant(..)                => (true, None),
+                Ply::Promotion(_, capture, piece) => (capture.is_some(), Some(piece.rank)),
+                Ply::Castling(..)                 => return false,
+            };
+
+            // The capture mark is optional, but must not be used falsely.
+            moved == rank
+                && Some(mv.to) == to
+                && from_file.is_none_or(|file| file == mv.from.file)
+                && from_rank.is_none_or(|rank| rank == mv.from.rank)
+                && (captures || !capture)
+                && promotes == promotion
+        }))
+    }
+}
+
+impl PlyOutputNotation for StandardAlgebraicNotation {
+    fn unparse_ply(&self, board: &Board, ply: &Ply) -> String {
+        let mut output = match *ply {
+            Ply::Castling(king, _) if king.to.file < king.from.file => "O-O-O".to_string(),
+            Ply::Castling(..) => "O-O".to_string(),
+            _ => self.unparse_movement(board, ply),
+        };
+
+        // Look at the board alone, so the suffix is there even once the
+        // game would be over.
+        if board.validate(ply).is_ok() {
+            let mut after = BitBoard::from(*board);
+            after.play(ply);
+            if after.is_check() {
+                output.push(if after.legal_plies().is_empty() { '#' } else { '+' });
+            }
         }
+        output
     }
 }
 
 impl StandardAlgebraicNotation {
-    fn parse_location(&self, file: Option<&str>, rank: Option<&str>) -> Option<Location> {
-        let file = match file.unwrap_or("") {
-            "a" => Some(0),
-            "b" => Some(1),
-            "c" => Some(2),
-            "d" => Some(3),
-            "e" => Some(4),
-            "f" => Some(5),
-            "g" => Some(6),
-            "h" => Some(7),
-            _   => None,
+    fn unparse_movement(&self, board: &Board, ply: &Ply) -> String {
+        let mv = ply.movement();
+        let moved = match *board.tile_at(&mv.from) {
+            Tile::Taken(piece) => piece.rank,
+            Tile::Empty        => Rank::Pawn,
+        };
+        let (capture, promotion) = match *ply {
+            Ply::Basic(_, capture)            => (capture.is_some(), None),
+            Ply::EnPassant(..)                => (true, None),
+            Ply::Promotion(_, capture, piece) => (capture.is_some(), Some(piece)),
+            Ply::Castling(..)                 => (false, None),
         };
-        let rank: Option<u8> = rank.unwrap_or("").parse::<u8>().ok();
-        match (file, rank) {
-            (Some(file), Some(rank)) if (0 < rank && rank < 9) => {
-                Some(Location { file: file, rank: rank - 1u8})
-            },
-            _ => None,
+
+        let mut output = String::new();
+        if moved == Rank::Pawn {
+            if capture {
+                output.push_str(&self.file_label(mv.from.file));
+            }
+        } else {
+            output.push(self.unparse_piece(&Piece { rank: moved, color: Color::White }));
+
+            // Name just enough of the origin to tell apart the other
+            // pieces of the same kind that could move to the same tile.
+            let rivals: Vec<Location> = board.legal_plies().into_iter()
+                .map(|other| other.movement())
+                .filter(|other| other.to == mv.to && other.from != mv.from)
+                .filter(|other| *board.tile_at(&other.from) == *board.tile_at(&mv.from))
+                .map(|other| other.from)
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|rival| rival.file != mv.from.file) {
+                    output.push_str(&self.file_label(mv.from.file));
+                } else if rivals.iter().all(|rival| rival.rank != mv.from.rank) {
+                    output.push_str(&self.rank_label(mv.from.rank));
+                } else {
+                    output.push_str(&self.unparse_location(&mv.from));
+                }
+            }
+        }
+
+        if capture {
+            output.push('x');
+        }
+        output.push_str(&self.unparse_location(&mv.to));
+        if let Some(piece) = promotion {
+            output.push('=');
+            output.push(self.unparse_piece(&Piece { rank: piece.rank, color: Color::White }));
+        }
+        output
+    }
+
+    // The single ply described, or None if it is impossible or ambiguous.
+    fn only<I: Iterator<Item=Ply>>(&self, mut plies: I) -> Option<Ply> {
+        match (plies.next(), plies.next()) {
+            (Some(ply), None) => Some(ply),
+            _                 => None,
+        }
+    }
+
+    fn parse_rank(&self, letter: Option<&str>) -> Option<Rank> {
+        match letter {
+            Some("N") => Some(Rank::Knight),
+            Some("B") => Some(Rank::Bishop),
+            Some("R") => Some(Rank::Rook),
+            Some("Q") => Some(Rank::Queen),
+            Some("K") => Some(Rank::King),
+            _         => None,
         }
     }
 
@@ -119,32 +221,134 @@ impl StandardAlgebraicNotation {
 
 #[cfg(test)]
 mod tests {
-    use board::Board;
+    use board::{Board, Tile};
+    use notation::{BoardInputNotation, ForsythEdwardsNotation};
+    use piece::{Piece, Rank};
+    use color::Color;
     use ply::{Ply, Location, Move};
     use super::StandardAlgebraicNotation;
-    use notation::PlyInputNotation;
+    use notation::{PlyInputNotation, PlyOutputNotation};
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    fn basic(from: (u8, u8), to: (u8, u8), capture: bool) -> Option<Ply> {
+        let to = Location { file: to.0, rank: to.1 };
+        Some(Ply::Basic(Move { from: Location { file: from.0, rank: from.1 }, to },
+                        if capture { Some(to) } else { None }))
+    }
+
+    #[test]
+    fn parse_ply_pawn() {
+        let notation = StandardAlgebraicNotation;
+        assert_eq!(notation.parse_ply(&Board::new(), "e4"), basic((4, 1), (4, 3), false));
+        assert_eq!(notation.parse_ply(&Board::new(), "e5"), None);
+
+        let board = from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2");
+        assert_eq!(notation.parse_ply(&board, "exd5"), basic((4, 3), (3, 4), true));
+        assert_eq!(notation.parse_ply(&board, "xd5"), None);
+    }
+
+    #[test]
+    fn parse_ply_piece() {
+        let notation = StandardAlgebraicNotation;
+        assert_eq!(notation.parse_ply(&Board::new(), "Nf3"), basic((6, 0), (5, 2), false));
+        assert_eq!(notation.parse_ply(&Board::new(), "Nf3!?"), basic((6, 0), (5, 2), false));
+        assert_eq!(notation.parse_ply(&Board::new(), "Nxf3"), None);
+        assert_eq!(notation.parse_ply(&Board::new(), "Bf3"), None);
+    }
+
+    #[test]
+    fn parse_ply_disambiguation() {
+        let notation = StandardAlgebraicNotation;
+        let board = from_fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1");
+        assert_eq!(notation.parse_ply(&board, "Rd1"), basic((0, 0), (3, 0), false));
+        assert_eq!(notation.parse_ply(&board, "Rf1"), basic((7, 0), (5, 0), false));
+
+        let board = from_fen("4k3/8/8/8/8/8/8/R2K3R w - - 0 1");
+        assert_eq!(notation.parse_ply(&board, "Rc1"), basic((0, 0), (2, 0), false));
+        assert_eq!(notation.parse_ply(&board, "Rg1"), basic((7, 0), (6, 0), false));
+
+        let board = from_fen("3k4/8/8/8/8/8/8/R6R w - - 0 1");
+        assert_eq!(notation.parse_ply(&board, "Rd1"), None);
+        assert_eq!(notation.parse_ply(&board, "Rad1"), basic((0, 0), (3, 0), false));
+        assert_eq!(notation.parse_ply(&board, "Rhd1+"), basic((7, 0), (3, 0), false));
+        assert_eq!(notation.parse_ply(&board, "Ra1d1"), basic((0, 0), (3, 0), false));
+
+        let board = from_fen("3k4/R7/8/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(notation.parse_ply(&board, "Ra4"), None);
+        assert_eq!(notation.parse_ply(&board, "R1a4"), basic((0, 0), (0, 3), false));
+    }
+
+    #[test]
+    fn parse_ply_castling() {
+        let notation = StandardAlgebraicNotation;
+        let board = from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1");
+        assert_eq!(notation.parse_ply(&board, "O-O"), Some(Ply::Castling(
+            Move { from: Location { file: 4, rank: 7 }, to: Location { file: 6, rank: 7 } },
+            Move { from: Location { file: 7, rank: 7 }, to: Location { file: 5, rank: 7 } },
+        )));
+        assert_eq!(notation.parse_ply(&board, "O-O-O"), Some(Ply::Castling(
+            Move { from: Location { file: 4, rank: 7 }, to: Location { file: 2, rank: 7 } },
+            Move { from: Location { file: 0, rank: 7 }, to: Location { file: 3, rank: 7 } },
+        )));
+        assert_eq!(notation.parse_ply(&Board::new(), "O-O"), None);
+    }
 
     #[test]
-    fn parse_ply_nocapture() {
+    fn parse_ply_promotion() {
         let notation = StandardAlgebraicNotation;
-        let ply = notation.parse_ply(&Board::new(), "a2 a3");
+        let mut board = from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        let queen = Piece { rank: Rank::Queen, color: Color::White };
+        let knight = Piece { rank: Rank::Knight, color: Color::White };
+        let mv = Move { from: Location { file: 0, rank: 6 }, to: Location { file: 0, rank: 7 } };
+        assert_eq!(notation.parse_ply(&board, "a8=Q+"), Some(Ply::Promotion(mv, None, queen)));
+        assert_eq!(notation.parse_ply(&board, "a8N"), Some(Ply::Promotion(mv, None, knight)));
+        assert_eq!(notation.parse_ply(&board, "a8"), None);
 
-        let expected = Some(Ply::Basic(Move {
-            from: Location { file: 0, rank: 1 },
-            to: Location { file: 0, rank: 2 },
-        }, None));
-        assert_eq!(ply, expected);
+        let mv = Move { from: Location { file: 0, rank: 6 }, to: Location { file: 1, rank: 7 } };
+        assert_eq!(notation.parse_ply(&board, "axb8=Q#"),
+                   Some(Ply::Promotion(mv, Some(Location { file: 1, rank: 7 }), queen)));
+
+        board.grid[7][1] = Tile::Empty;
+        assert_eq!(notation.parse_ply(&board, "axb8=Q"), None);
     }
 
     #[test]
-    fn parse_ply_capture() {
+    fn parse_ply_enpassant() {
         let notation = StandardAlgebraicNotation;
-        let ply = notation.parse_ply(&Board::new(), "h8 h7");
+        let board = from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        assert_eq!(notation.parse_ply(&board, "exd6"), Some(Ply::EnPassant(Move {
+            from: Location { file: 4, rank: 4 },
+            to: Location { file: 3, rank: 5 },
+        }, Location { file: 3, rank: 4 })));
+    }
 
-        let expected = Some(Ply::Basic(Move {
-            from: Location { file: 7, rank: 7 },
-            to: Location { file: 7, rank: 6 },
-        }, Some(Location { file: 7, rank: 6 })));
-        assert_eq!(ply, expected);
+    #[test]
+    fn unparse_ply() {
+        let notation = StandardAlgebraicNotation;
+        let cases = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e4"),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "Nf3"),
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", "exd5"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "O-O"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "O-O-O"),
+            ("3k4/8/8/8/8/8/8/R6R w - - 0 1", "Rad1+"),
+            ("3k4/8/8/8/8/8/8/R6R w - - 0 1", "Rhd1+"),
+            ("3k4/R7/8/8/8/8/8/R3K3 w - - 0 1", "R1a4"),
+            ("8/8/8/7k/8/Q7/8/Q1Q4K w - - 0 1", "Qa1b2"),
+            ("8/8/8/7k/8/Q7/8/Q1Q4K w - - 0 1", "Qcb2"),
+            ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=Q+"),
+            ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8=N"),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#"),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 150 100", "Ra8#"),
+        ];
+        for &(fen, san) in cases.iter() {
+            let board = from_fen(fen);
+            let ply = notation.parse_ply(&board, san).unwrap();
+            assert_eq!(notation.unparse_ply(&board, &ply), san);
+        }
     }
 }
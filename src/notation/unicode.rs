@@ -0,0 +1,74 @@
+use board::Tile;
+use color::Color;
+use piece::Rank;
+use ply::Location;
+
+use notation::{BoardOutputNotationHelper, StandardAlgebraicNotation};
+
+
+/// Unicode Notation
+/// Draws pieces with the Unicode chess glyphs, and labels files and
+/// ranks as Standard Algebraic Notation does.
+///
+/// White: ♔ ♕ ♖ ♗ ♘ ♙
+/// Black: ♚ ♛ ♜ ♝ ♞ ♟
+pub struct UnicodeNotation;
+
+impl BoardOutputNotationHelper for UnicodeNotation {
+    fn unparse_tile(&self, tile: &Tile) -> String {
+        let piece = match *tile {
+            Tile::Empty        => return " ".to_string(),
+            Tile::Taken(piece) => piece,
+        };
+        let glyph = match (piece.color, piece.rank) {
+            (Color::White, Rank::King)   => '♔',
+            (Color::White, Rank::Queen)  => '♕',
+            (Color::White, Rank::Rook)   => '♖',
+            (Color::White, Rank::Bishop) => '♗',
+            (Color::White, Rank::Knight) => '♘',
+            (Color::White, Rank::Pawn)   => '♙',
+            (Color::Black, Rank::King)   => '♚',
+            (Color::Black, Rank::Queen)  => '♛',
+            (Color::Black, Rank::Rook)   => '♜',
+            (Color::Black, Rank::Bishop) => '♝',
+            (Color::Black, Rank::Knight) => '♞',
+            (Color::Black, Rank::Pawn)   => '♟',
+        };
+        glyph.to_string()
+    }
+
+    fn unparse_location(&self, location: &Location) -> String {
+        StandardAlgebraicNotation.unparse_location(location)
+    }
+
+    fn file_label(&self, file: u8) -> String {
+        StandardAlgebraicNotation.file_label(file)
+    }
+
+    fn rank_label(&self, rank: u8) -> String {
+        StandardAlgebraicNotation.rank_label(rank)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::{Board, Tile};
+    use color::Color;
+    use piece::{Piece, Rank};
+    use ply::Location;
+    use notation::BoardOutputNotationHelper;
+    use super::UnicodeNotation;
+
+    #[test]
+    fn unparse_tile() {
+        let notation = UnicodeNotation;
+        let board = Board::new();
+        let row: String = (0..8).map(|file| notation.unparse_tile(&board.grid[7][file])).collect();
+        assert_eq!(row, "♜♞♝♛♚♝♞♜");
+        assert_eq!(notation.unparse_tile(&board.grid[1][0]), "♙");
+        assert_eq!(notation.unparse_tile(&Tile::Empty), " ");
+        assert_eq!(notation.unparse_tile(&Tile::Taken(Piece { rank: Rank::King, color: Color::White })), "♔");
+        assert_eq!(notation.unparse_location(&Location { rank: 2, file: 4 }), "e3");
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+
+use board::{Board, Tile};
+use color::Color;
+use game::Game;
+use piece::Rank;
+
+/// Why a game came to an end.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Reason {
+    /// The side to move is in check and cannot escape.
+    Checkmate,
+    /// The side to move is not in check, but has no legal plies.
+    Stalemate,
+    /// Fifty moves by each player without a pawn move or a capture,
+    /// claimed by a player.
+    FiftyMoveRule,
+    /// The same position has come up three times, claimed by a player.
+    ThreefoldRepetition,
+    /// Seventy-five moves by each player without a pawn move or a capture.
+    SeventyFiveMoveRule,
+    /// The same position has come up five times.
+    FivefoldRepetition,
+    /// Neither player has the pieces left to deliver checkmate.
+    InsufficientMaterial,
+    /// A player gave up the game.
+    Resignation,
+}
+
+/// The result of a finished game. A draw has no winner.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Outcome {
+    pub winner: Option<Color>,
+    pub reason: Reason,
+}
+
+impl Outcome {
+    /// The result as PGN and most protocols write it: 1-0, 0-1 or 1/2-1/2.
+    pub fn score(&self) -> &'static str {
+        match self.winner {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None               => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.reason {
+            Reason::Checkmate            => "checkmate",
+            Reason::Stalemate            => "stalemate",
+            Reason::FiftyMoveRule        => "the fifty-move rule",
+            Reason::ThreefoldRepetition  => "threefold repetition",
+            Reason::SeventyFiveMoveRule  => "the seventy-five-move rule",
+            Reason::FivefoldRepetition   => "fivefold repetition",
+            Reason::InsufficientMaterial => "insufficient material",
+            Reason::Resignation          => "resignation",
+        };
+        match self.winner {
+            Some(Color::White) => write!(f, "White wins by {}", reason),
+            Some(Color::Black) => write!(f, "Black wins by {}", reason),
+            None               => write!(f, "Draw by {}", reason),
+        }
+    }
+}
+
+impl Game {
+    /// How the game ended, or None if it is still being played.
+    /// Draws that FIDE rules leave for a player to claim do not end the
+    /// game here; see `claimable_draw`.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let reason = if self.board.legal_plies().is_empty() {
+            if self.board.is_check() {
+                return Some(Outcome {
+                    winner: Some(self.board.color.other()),
+                    reason: Reason::Checkmate,
+                });
+            }
+            Reason::Stalemate
+        } else if self.board.is_insufficient_material() {
+            Reason::InsufficientMaterial
+        } else if self.repetitions() >= 5 {
+            Reason::FivefoldRepetition
+        } else if self.board.halfmove_clock >= 150 {
+            Reason::SeventyFiveMoveRule
+        } else {
+            return None;
+        };
+        Some(Outcome { winner: None, reason })
+    }
+
+    /// The draw the side to move may claim, by threefold repetition or
+    /// the fifty-move rule, or None if there is none. Play goes on
+    /// until a player claims it.
+    pub fn claimable_draw(&self) -> Option<Outcome> {
+        let reason = if self.repetitions() >= 3 {
+            Reason::ThreefoldRepetition
+        } else if self.board.halfmove_clock >= 100 {
+            Reason::FiftyMoveRule
+        } else {
+            return None;
+        };
+        Some(Outcome { winner: None, reason })
+    }
+
+    /// How many times the current position has come up in the game,
+    /// counting this time.
+    pub fn repetitions(&self) -> usize {
+        let hash = self.hash();
+        self.log.iter()
+            .filter(|entry| entry.hash == hash)
+            .count() + 1
+    }
+}
+
+impl Board {
+    /// Whether only kings remain, or a lone minor piece, or bishops
+    /// that all stand on tiles of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut knights = 0;
+        let mut bishop_tiles = [false; 2];
+        for (rank, row) in self.grid.iter().enumerate() {
+            for (file, tile) in row.iter().enumerate() {
+                match *tile {
+                    Tile::Empty => (),
+                    Tile::Taken(piece) => match piece.rank {
+                        Rank::King   => (),
+                        Rank::Knight => knights += 1,
+                        Rank::Bishop => bishop_tiles[(rank + file) % 2] = true,
+                        _            => return false,
+                    },
+                }
+            }
+        }
+
+        let bishops = bishop_tiles.iter().filter(|&&taken| taken).count();
+        (knights == 0 && bishops <= 1) || (knights == 1 && bishops == 0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use color::Color;
+    use game::Game;
+    use movegen::IllegalPly;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation, StandardAlgebraicNotation};
+    use super::{Outcome, Reason};
+
+    fn from_fen(fen: &str) -> Game {
+        Game::from_board(ForsythEdwardsNotation.parse_board(fen).unwrap())
+    }
+
+    fn play(game: &mut Game, plies: &[&str]) {
+        for san in plies {
+            let ply = game.parse_ply(&StandardAlgebraicNotation, san).unwrap();
+            game.play(&ply).unwrap();
+        }
+    }
+
+    #[test]
+    fn in_progress() {
+        assert_eq!(Game::new().outcome(), None);
+    }
+
+    #[test]
+    fn checkmate() {
+        let mut game = Game::new();
+        play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(game.outcome(), Some(Outcome {
+            winner: Some(Color::Black),
+            reason: Reason::Checkmate,
+        }));
+        let outcome = game.outcome().unwrap();
+        assert_eq!(outcome.score(), "0-1");
+        assert_eq!(outcome.to_string(), "Black wins by checkmate");
+    }
+
+    #[test]
+    fn resignation() {
+        let outcome = Outcome { winner: Some(Color::White), reason: Reason::Resignation };
+        assert_eq!(outcome.score(), "1-0");
+        assert_eq!(outcome.to_string(), "White wins by resignation");
+    }
+
+    #[test]
+    fn stalemate() {
+        let game = from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(game.outcome(), Some(Outcome { winner: None, reason: Reason::Stalemate }));
+        assert_eq!(game.outcome().unwrap().score(), "1/2-1/2");
+        assert_eq!(game.outcome().unwrap().to_string(), "Draw by stalemate");
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let mut game = from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80");
+        assert_eq!(game.claimable_draw(), None);
+        play(&mut game, &["Ra2"]);
+        assert_eq!(game.claimable_draw(), Some(Outcome { winner: None, reason: Reason::FiftyMoveRule }));
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["Kd8"]);
+        assert_eq!(game.board.halfmove_clock, 101);
+    }
+
+    #[test]
+    fn seventy_five_move_rule() {
+        let mut game = from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 100");
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["Ra2"]);
+        assert_eq!(game.outcome(), Some(Outcome { winner: None, reason: Reason::SeventyFiveMoveRule }));
+        assert_eq!(game.outcome().unwrap().to_string(), "Draw by the seventy-five-move rule");
+        let ply = game.board.legal_plies()[0];
+        assert_eq!(game.play(&ply), Err(IllegalPly::GameOver));
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut game = Game::new();
+        play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"]);
+        assert_eq!(game.repetitions(), 2);
+        assert_eq!(game.claimable_draw(), None);
+        play(&mut game, &["Ng8"]);
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.claimable_draw(), Some(Outcome {
+            winner: None,
+            reason: Reason::ThreefoldRepetition,
+        }));
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["Nf3"]);
+        assert_eq!(game.claimable_draw(), Some(Outcome {
+            winner: None,
+            reason: Reason::ThreefoldRepetition,
+        }));
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        let mut game = Game::new();
+        for _ in 0..3 {
+            play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+        assert_eq!(game.repetitions(), 4);
+        assert_eq!(game.outcome(), None);
+        play(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(game.repetitions(), 5);
+        assert_eq!(game.outcome(), Some(Outcome {
+            winner: None,
+            reason: Reason::FivefoldRepetition,
+        }));
+        let ply = game.board.legal_plies()[0];
+        assert_eq!(game.play(&ply), Err(IllegalPly::GameOver));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let cases = [
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", true),
+            ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+            ("3bk3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ];
+        for &(fen, expected) in cases.iter() {
+            let board: Board = ForsythEdwardsNotation.parse_board(fen).unwrap();
+            assert_eq!(board.is_insufficient_material(), expected, "{}", fen);
+        }
+
+        let game = from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(game.outcome(), Some(Outcome {
+            winner: None,
+            reason: Reason::InsufficientMaterial,
+        }));
+    }
+}
@@ -0,0 +1,124 @@
+use bitboard::BitBoard;
+use board::Board;
+use ply::Ply;
+
+/// Count the leaf nodes of the tree of legal plies from the board,
+/// down to the given depth. Comparing the counts for well known
+/// positions against published ones checks the move generator.
+///
+/// https://www.chessprogramming.org/Perft
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    count(&BitBoard::from(*board), depth)
+}
+
+/// The perft counts below each legal ply from the board, so that a
+/// mismatch can be narrowed down one ply at a time. They add up to
+/// `perft(board, depth)`, except at depth 0, where there are no plies
+/// to divide the one node into and the list is empty.
+pub fn divide(board: &Board, depth: u32) -> Vec<(Ply, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let bitboard = BitBoard::from(*board);
+    bitboard.legal_plies().into_iter().map(|ply| {
+        let mut after = bitboard;
+        after.play(&ply);
+        (ply, count(&after, depth - 1))
+    }).collect()
+}
+
+fn count(bitboard: &BitBoard, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let plies = bitboard.legal_plies();
+    if depth == 1 {
+        return plies.len() as u64;
+    }
+    plies.iter().map(|ply| {
+        let mut after = *bitboard;
+        after.play(ply);
+        count(&after, depth - 1)
+    }).sum()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use game::Game;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation};
+    use super::{perft, divide};
+
+    // https://www.chessprogramming.org/Perft_Results
+    const REFERENCE: [(&str, &[u64]); 5] = [
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]),
+        ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+        ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+        ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]),
+    ];
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    // Perft through Board and Game rather than BitBoard, to check the
+    // two move generators against each other.
+    fn board_perft(game: &mut Game, depth: u32) -> u64 {
+        let plies = game.board.legal_plies();
+        if depth <= 1 {
+            return if depth == 0 { 1 } else { plies.len() as u64 };
+        }
+        plies.iter().map(|ply| {
+            game.play(ply).unwrap();
+            let nodes = board_perft(game, depth - 1);
+            game.undo();
+            nodes
+        }).sum()
+    }
+
+    #[test]
+    fn initial() {
+        let board = Board::new();
+        let expected = [1, 20, 400, 8902, 197281];
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&board, depth as u32), nodes);
+        }
+    }
+
+    #[test]
+    fn reference_positions() {
+        for &(fen, expected) in REFERENCE.iter() {
+            let board = from_fen(fen);
+            for (depth, &nodes) in expected.iter().enumerate() {
+                assert_eq!(perft(&board, depth as u32 + 1), nodes, "{} at depth {}", fen, depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn board_reference_positions() {
+        let mut game = Game::new();
+        for (depth, &nodes) in [1, 20, 400, 8902].iter().enumerate() {
+            assert_eq!(board_perft(&mut game, depth as u32), nodes);
+        }
+        for &(fen, expected) in REFERENCE.iter() {
+            let mut game = Game::from_board(from_fen(fen));
+            for (depth, &nodes) in expected.iter().take(2).enumerate() {
+                assert_eq!(board_perft(&mut game, depth as u32 + 1), nodes, "{} at depth {}", fen, depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board = from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let counts = divide(&board, 2);
+        assert_eq!(counts.len(), 48);
+        assert_eq!(counts.iter().map(|&(_, nodes)| nodes).sum::<u64>(), 2039);
+
+        assert_eq!(divide(&board, 1).iter().map(|&(_, nodes)| nodes).sum::<u64>(), perft(&board, 1));
+        assert!(divide(&board, 0).is_empty());
+    }
+}
@@ -6,6 +6,20 @@ pub struct Location {
     pub file: u8,
 }
 
+impl Location {
+    /// The location a number of ranks and files away from this one,
+    /// or None if that would fall off the edge of the board.
+    pub fn offset(&self, ranks: i8, files: i8) -> Option<Location> {
+        let rank = self.rank as i8 + ranks;
+        let file = self.file as i8 + files;
+        if (0..8).contains(&rank) && (0..8).contains(&file) {
+            Some(Location { rank: rank as u8, file: file as u8 })
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Move {
     pub from: Location,
@@ -20,6 +34,17 @@ pub enum Ply {
     Castling(Move, Move),
 }
 
+impl Ply {
+    /// The move of the piece the player picks up. When castling,
+    /// that is the king.
+    pub fn movement(&self) -> Move {
+        match *self {
+            Ply::Basic(mv, _) | Ply::EnPassant(mv, _) | Ply::Promotion(mv, _, _) => mv,
+            Ply::Castling(king, _) => king,
+        }
+    }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -40,6 +65,14 @@ mod tests {
         assert!(loc.file == 2);
     }
 
+    #[test]
+    fn location_offset() {
+        let loc = Location { rank: 0, file: 6 };
+        assert!(loc.offset(2, 1) == Some(Location { rank: 2, file: 7 }));
+        assert!(loc.offset(2, 2).is_none());
+        assert!(loc.offset(-1, 0).is_none());
+    }
+
     #[test]
     fn move_eq() {
         let mov = Move {
@@ -120,6 +153,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn ply_movement() {
+        let mov = Move {
+            from: Location { rank: 0, file: 4 },
+            to: Location { rank: 0, file: 6 },
+        };
+        let mov2 = Move {
+            from: Location { rank: 0, file: 7 },
+            to: Location { rank: 0, file: 5 },
+        };
+        assert!(Ply::Basic(mov, None).movement() == mov);
+        assert!(Ply::Castling(mov, mov2).movement() == mov);
+    }
+
     #[test]
     fn ply_castling() {
         let mov = Move {
@@ -0,0 +1,126 @@
+use board::{Board, Tile, Castling};
+use color::Color;
+use ply::{Location, Ply};
+
+// One key per piece kind and color on each tile, then one for Black
+// to move, one per castling right and one per en passant file.
+const PIECES: usize = 0;
+const SIDE: usize = 12 * 64;
+const CASTLING: usize = SIDE + 1;
+const ENPASSANT: usize = CASTLING + 4;
+const KEYS: [u64; ENPASSANT + 8] = keys();
+
+// Fixed pseudo-random keys from a SplitMix64 sequence, so that hashes
+// are the same from one run to the next.
+const fn keys() -> [u64; ENPASSANT + 8] {
+    let mut keys = [0; ENPASSANT + 8];
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut index = 0;
+    while index < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[index] = z ^ (z >> 31);
+        index += 1;
+    }
+    keys
+}
+
+/// The key for whatever stands on the tile, if anything.
+pub(crate) fn tile_key(board: &Board, location: Location) -> u64 {
+    match *board.tile_at(&location) {
+        Tile::Taken(piece) => {
+            let kind = piece.color as usize * 6 + piece.rank as usize;
+            KEYS[PIECES + kind * 64 + location.rank as usize * 8 + location.file as usize]
+        },
+        Tile::Empty => 0,
+    }
+}
+
+/// The key for everything but the pieces: the side to move, castling
+/// rights and en passant file.
+pub(crate) fn state_key(board: &Board) -> u64 {
+    let Castling { white_king, white_queen, black_king, black_queen } = board.castling;
+    let mut key = 0;
+    if board.color == Color::Black {
+        key ^= KEYS[SIDE];
+    }
+    for (index, &right) in [white_king, white_queen, black_king, black_queen].iter().enumerate() {
+        if right {
+            key ^= KEYS[CASTLING + index];
+        }
+    }
+    if let Some(location) = board.enpassant {
+        key ^= KEYS[ENPASSANT + location.file as usize];
+    }
+    key
+}
+
+/// The tiles whose contents the ply changes, each listed once.
+pub(crate) fn touched(ply: &Ply) -> Vec<Location> {
+    let mut locations = match *ply {
+        Ply::Basic(mv, capture) | Ply::Promotion(mv, capture, _) => {
+            let mut locations = vec![mv.from, mv.to];
+            locations.extend(capture);
+            locations
+        },
+        Ply::EnPassant(mv, capture) => vec![mv.from, mv.to, capture],
+        Ply::Castling(king, rook)   => vec![king.from, king.to, rook.from, rook.to],
+    };
+    locations.sort_by_key(|location| (location.rank, location.file));
+    locations.dedup();
+    locations
+}
+
+impl Board {
+    /// A 64-bit Zobrist hash of the position: the pieces, the side to
+    /// move, castling rights and en passant file, but not the clocks.
+    /// Equal positions always hash the same; different positions hash
+    /// the same only by rare accident.
+    ///
+    /// https://www.chessprogramming.org/Zobrist_Hashing
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = state_key(self);
+        for rank in 0..8 {
+            for file in 0..8 {
+                hash ^= tile_key(self, Location { rank, file });
+            }
+        }
+        hash
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use board::Board;
+    use notation::{BoardInputNotation, ForsythEdwardsNotation};
+
+    fn from_fen(fen: &str) -> Board {
+        ForsythEdwardsNotation.parse_board(fen).unwrap()
+    }
+
+    #[test]
+    fn zobrist() {
+        let board = Board::new();
+        assert_eq!(board.zobrist(), Board::new().zobrist());
+        assert_eq!(board.zobrist(), from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 12 30").zobrist());
+
+        let others = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq d3 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKB1R w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBRN w KQkq - 0 1",
+        ];
+        for (index, fen) in others.iter().enumerate() {
+            let hash = from_fen(fen).zobrist();
+            assert!(hash != board.zobrist(), "{}", fen);
+            for other in others[index + 1..].iter() {
+                assert!(hash != from_fen(other).zobrist(), "{} {}", fen, other);
+            }
+        }
+    }
+}